@@ -0,0 +1,430 @@
+//! `pkgrank analyze`: rank packages by centrality.
+
+use crate::centrality::{self, Convergence, PageRankConfig};
+use crate::git::Churn;
+use crate::graph::DepGraph;
+use crate::group::GroupBy;
+use crate::license::{self, LicenseReport};
+use crate::nested;
+use crate::targets::TargetExclusive;
+use cargo_metadata::{Metadata, PackageId};
+use clap::ValueEnum;
+use petgraph::prelude::*;
+use petgraph::visit::{Bfs, Reversed, Walker};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Write;
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, ValueEnum, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Metric {
+    Pagerank,
+    /// Personalized PageRank seeded with `--seed`
+    Ppr,
+    Indegree,
+    Outdegree,
+    Betweenness,
+    /// PageRank scaled by recent git churn (needs `--churn-window`)
+    Churn,
+    /// Stable-platform score: transitive dependents times k-core depth,
+    /// discounted by churn (over `--churn-window`, default 90 days)
+    Foundation,
+}
+
+/// Churn window for `--metric foundation` when `--churn-window` is not given.
+pub const FOUNDATION_CHURN_DAYS: u64 = 90;
+
+/// The churn-independent part of the foundation score: the share of other
+/// packages that depend on a node (transitively), times its coreness
+/// relative to the deepest core.
+pub fn foundation(graph: &DepGraph) -> Vec<f64> {
+    let n = graph.node_count();
+    if n <= 1 {
+        return vec![0.0; n];
+    }
+    let core = centrality::coreness(graph);
+    let max_core = core.iter().copied().fold(0.0, f64::max).max(1.0);
+    graph
+        .node_indices()
+        .map(|i| {
+            let dependents = Bfs::new(Reversed(graph), i).iter(Reversed(graph)).count() - 1;
+            dependents as f64 / (n - 1) as f64 * core[i.index()] / max_core
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AnalyzeRow {
+    pub rank: usize,
+    pub name: String,
+    pub version: String,
+    pub score: f64,
+    pub workspace_member: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Nested workspace the crate came from (with `--nested`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    /// Recent churn of path packages (with `--churn-window`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub churn: Option<Churn>,
+    /// Third-party dependencies by distance, for workspace members (with `--depths`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depths: Option<DepthHistogram>,
+}
+
+/// How many hops separate a first-party crate from its third-party dependencies.
+#[derive(Debug, Clone, Copy, Default, Serialize, JsonSchema)]
+pub struct DepthHistogram {
+    pub direct: usize,
+    pub two_hop: usize,
+    /// Three hops or more.
+    pub deeper: usize,
+    pub max_depth: usize,
+}
+
+/// Shortest-path depth of every third-party package reachable from `node`.
+pub fn depth_histogram(graph: &DepGraph, node: NodeIndex) -> DepthHistogram {
+    let mut hist = DepthHistogram::default();
+    let mut seen = vec![false; graph.node_count()];
+    seen[node.index()] = true;
+    let mut frontier = vec![node];
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut next = Vec::new();
+        for v in frontier {
+            for w in graph.neighbors_directed(v, Direction::Outgoing) {
+                if std::mem::replace(&mut seen[w.index()], true) {
+                    continue;
+                }
+                next.push(w);
+                if graph[w].workspace_member {
+                    continue;
+                }
+                match depth {
+                    1 => hist.direct += 1,
+                    2 => hist.two_hop += 1,
+                    _ => hist.deeper += 1,
+                }
+                hist.max_depth = depth;
+            }
+        }
+        frontier = next;
+    }
+    hist
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AnalyzeOut {
+    pub metric: Metric,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus: Option<Focus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<GroupBy>,
+    /// Effective PageRank settings (PageRank-family metrics only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagerank_config: Option<PageRankConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub convergence: Option<Convergence>,
+    pub nodes: usize,
+    pub edges: usize,
+    pub rows: Vec<AnalyzeRow>,
+    pub diagnostics: Diagnostics,
+    /// Signs the graph is too degenerate for the ranking to mean much.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Packages only one of the `--targets` pulls in.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub target_exclusive: Vec<TargetExclusive>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub licenses: Option<LicenseReport>,
+}
+
+/// The neighbourhood scoring was restricted to (`--focus`).
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Focus {
+    pub package: String,
+    pub radius: usize,
+}
+
+/// Dependency edges between workspace members, written by `analyze --out`
+/// so `compare-runs` can diff the first-party structure.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WorkspaceGraph {
+    /// Sorted `[dependent, dependency]` crate names.
+    pub edges: Vec<[String; 2]>,
+}
+
+pub fn workspace_graph(graph: &DepGraph) -> WorkspaceGraph {
+    let mut edges: Vec<[String; 2]> = graph
+        .edge_references()
+        .filter(|e| graph[e.source()].workspace_member && graph[e.target()].workspace_member)
+        .filter(|e| e.source() != e.target())
+        .map(|e| {
+            [
+                graph[e.source()].name.clone(),
+                graph[e.target()].name.clone(),
+            ]
+        })
+        .collect();
+    edges.sort();
+    edges.dedup();
+    WorkspaceGraph { edges }
+}
+
+/// One `analyze --stream` event.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Progress<'a> {
+    /// A stage of the run finished.
+    Phase { phase: Phase },
+    /// One PageRank iteration; `diff` is the L1 distance to the previous one.
+    Iteration { iteration: usize, diff: f64 },
+    /// The final payload, as printed by `--json`.
+    Result(&'a AnalyzeOut),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Metadata loaded and graph built.
+    Loaded,
+    /// Scores computed.
+    Scored,
+}
+
+/// An event as written to stdout: one JSON object per line.
+#[derive(Serialize, JsonSchema)]
+pub struct StreamLine<'a> {
+    /// Milliseconds since the run started.
+    pub elapsed_ms: u64,
+    #[serde(flatten)]
+    pub event: Progress<'a>,
+}
+
+/// Print `event` as one NDJSON line and flush, so readers see it right away.
+pub fn emit(start: Instant, event: Progress) {
+    let line = StreamLine {
+        elapsed_ms: start.elapsed().as_millis() as u64,
+        event,
+    };
+    let mut stdout = std::io::stdout().lock();
+    // A closed pipe means the reader stopped listening; nothing to report to.
+    let _ = serde_json::to_writer(&mut stdout, &line)
+        .map_err(std::io::Error::from)
+        .and_then(|()| writeln!(stdout))
+        .and_then(|()| stdout.flush());
+}
+
+/// Checks for graphs whose ranking would look confident but say nothing.
+pub fn warnings(graph: &DepGraph, scores: &[f64], workspace_only: bool) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if graph.node_count() == 0 {
+        return warnings;
+    }
+    if graph.edge_count() == 0 {
+        warnings.push(format!(
+            "graph has {} nodes and no edges; every score is the baseline",
+            graph.node_count()
+        ));
+        return warnings;
+    }
+
+    let total: f64 = scores.iter().sum();
+    if let Some((i, max)) = scores.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1))
+        && total > 0.0
+        && max / total > 0.9
+    {
+        warnings.push(format!(
+            "{:.0}% of the score mass is on {}",
+            100.0 * max / total,
+            graph[NodeIndex::new(i)].name
+        ));
+    }
+
+    if workspace_only
+        && graph.node_weights().filter(|p| p.workspace_member).count() > 1
+        && !graph
+            .edge_references()
+            .any(|e| graph[e.source()].workspace_member && graph[e.target()].workspace_member)
+    {
+        warnings.push(
+            "no edges between workspace members; --workspace-only ranks isolated crates"
+                .to_string(),
+        );
+    }
+    warnings
+}
+
+/// Packages that are in `cargo metadata` but don't show up (or show up
+/// oddly) in the ranking, so node counts can be reconciled.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Diagnostics {
+    /// Non-member packages dropped from the rows by `--workspace-only`.
+    pub excluded_by_workspace_only: usize,
+    /// Path dependencies whose manifest lives outside the workspace root.
+    pub path_deps_outside_workspace: Vec<String>,
+    /// Packages listed in metadata but absent from the resolve graph
+    /// (ranked as isolated nodes).
+    pub unresolved: Vec<String>,
+    /// Nested workspaces within `NESTED_HINT_DEPTH` directories of the root
+    /// whose crates are not in the graph (pass `--nested`). Empty with
+    /// `--nested`, which finds them all.
+    pub nested_workspaces_skipped: Vec<String>,
+}
+
+/// How deep `diagnostics` looks for nested workspaces when `--nested` is off.
+const NESTED_HINT_DEPTH: usize = 2;
+
+pub fn diagnostics(
+    metadata: &Metadata,
+    graph: &DepGraph,
+    workspace_only: bool,
+    nested: bool,
+) -> Diagnostics {
+    let excluded_by_workspace_only = if workspace_only {
+        graph
+            .node_weights()
+            .filter(|pkg| !pkg.workspace_member)
+            .count()
+    } else {
+        0
+    };
+
+    let root = metadata.workspace_root.as_std_path();
+    let mut path_deps_outside_workspace: Vec<String> = graph
+        .node_weights()
+        .filter(|pkg| {
+            pkg.source.is_none() && !pkg.workspace_member && !pkg.manifest_path.starts_with(root)
+        })
+        .map(|pkg| {
+            let dir = pkg.manifest_path.parent().unwrap_or(&pkg.manifest_path);
+            format!("{}@{} ({})", pkg.name, pkg.version, dir.display())
+        })
+        .collect();
+    path_deps_outside_workspace.sort();
+
+    let resolved: HashSet<&PackageId> = metadata
+        .resolve
+        .iter()
+        .flat_map(|r| r.nodes.iter().map(|n| &n.id))
+        .collect();
+    let mut unresolved: Vec<String> = metadata
+        .packages
+        .iter()
+        .filter(|pkg| !resolved.contains(&pkg.id))
+        .map(|pkg| format!("{}@{}", pkg.name, pkg.version))
+        .collect();
+    unresolved.sort();
+
+    let merged: HashSet<&str> = graph
+        .node_weights()
+        .filter_map(|pkg| pkg.workspace.as_deref())
+        .collect();
+    let nested_workspaces_skipped = if nested {
+        vec![]
+    } else {
+        nested::find_near(root, NESTED_HINT_DEPTH)
+            .iter()
+            .map(|dir| nested::label(root, dir))
+            .filter(|label| !merged.contains(label.as_str()))
+            .collect()
+    };
+
+    Diagnostics {
+        excluded_by_workspace_only,
+        path_deps_outside_workspace,
+        unresolved,
+        nested_workspaces_skipped,
+    }
+}
+
+pub fn print_text(out: &AnalyzeOut, top: usize) {
+    match &out.focus {
+        Some(f) => println!(
+            "Top {} by {:?} within {} hops of {}:",
+            top, out.metric, f.radius, f.package
+        ),
+        None => println!("Top {} by {:?}:", top, out.metric),
+    }
+    println!("{:─<50}", "");
+    for row in &out.rows {
+        let churn = row
+            .churn
+            .map(|c| format!("  {:4} commits {:7} lines", c.commits, c.lines))
+            .unwrap_or_default();
+        let depths = row
+            .depths
+            .map(|d| {
+                format!(
+                    "  {:>11}",
+                    format!("{}/{}/{}", d.direct, d.two_hop, d.deeper)
+                )
+            })
+            .unwrap_or_default();
+        let workspace = row
+            .workspace
+            .as_deref()
+            .map(|w| format!("  [{}]", w))
+            .unwrap_or_default();
+        let line = format!(
+            "{:3}. {:40} {:.6}{}{}{}  {}",
+            row.rank,
+            row.name,
+            row.score,
+            churn,
+            depths,
+            workspace,
+            row.owner.as_deref().unwrap_or("")
+        );
+        println!("{}", line.trim_end());
+    }
+    if let Some(c) = out.convergence
+        && !c.converged
+    {
+        println!(
+            "\nwarning: PageRank did not converge after {} iterations (diff {:.2e})",
+            c.iterations, c.final_diff
+        );
+    }
+    if out.rows.iter().any(|r| r.depths.is_some()) {
+        println!("\n(x/y/z: third-party deps reached directly / in 2 hops / deeper)");
+    }
+    println!("\n{} nodes, {} edges", out.nodes, out.edges);
+    let d = &out.diagnostics;
+    if d.excluded_by_workspace_only > 0 {
+        println!(
+            "{} non-member packages excluded by --workspace-only",
+            d.excluded_by_workspace_only
+        );
+    }
+    if !d.path_deps_outside_workspace.is_empty() {
+        println!(
+            "path deps outside the workspace: {}",
+            d.path_deps_outside_workspace.join(", ")
+        );
+    }
+    if !d.unresolved.is_empty() {
+        println!("in metadata but not resolved: {}", d.unresolved.join(", "));
+    }
+    if !d.nested_workspaces_skipped.is_empty() {
+        println!(
+            "nested workspaces not analyzed (pass --nested): {}",
+            d.nested_workspaces_skipped.join(", ")
+        );
+    }
+    for t in &out.target_exclusive {
+        println!(
+            "only on {} ({}): {}",
+            t.target,
+            t.packages.len(),
+            t.packages.join(", ")
+        );
+    }
+    if let Some(licenses) = &out.licenses {
+        license::print_text(licenses);
+    }
+}
@@ -0,0 +1,266 @@
+//! Files pkgrank writes and reads back, in one place so writers and
+//! readers agree on names and layout.
+
+use crate::time;
+use anyhow::Context;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Directory under the workspace root used when no location is given.
+pub const DEFAULT_DIR: &str = ".pkgrank";
+
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema)]
+pub struct Artifact {
+    pub name: &'static str,
+    /// File name within the artifact directory.
+    pub file: &'static str,
+    /// Rust type of the payload under the envelope's `data`.
+    pub schema: &'static str,
+    /// Bumped whenever the payload changes incompatibly.
+    pub version: u32,
+    pub producer: &'static str,
+}
+
+pub const ANALYZE: Artifact = Artifact {
+    name: "analyze",
+    file: "analyze.json",
+    schema: "AnalyzeOut",
+    version: 2,
+    producer: "pkgrank analyze --out",
+};
+
+pub const WORKSPACE_GRAPH: Artifact = Artifact {
+    name: "workspace-graph",
+    file: "workspace_graph.json",
+    schema: "WorkspaceGraph",
+    version: 2,
+    producer: "pkgrank analyze --out",
+};
+
+pub const MODULES: Artifact = Artifact {
+    name: "modules",
+    file: "modules.json",
+    schema: "ModulesOut",
+    version: 2,
+    producer: "pkgrank modules --out",
+};
+
+pub const DIGEST_STATE: Artifact = Artifact {
+    name: "digest-state",
+    file: "digest.json",
+    schema: "DigestState",
+    version: 2,
+    producer: "pkgrank digest",
+};
+
+pub const SNAPSHOT: Artifact = Artifact {
+    name: "snapshot",
+    file: "snapshot.json",
+    schema: "SnapshotManifest",
+    version: 2,
+    producer: "pkgrank snapshot",
+};
+
+pub const LICENSES: Artifact = Artifact {
+    name: "licenses",
+    file: "licenses.json",
+    schema: "LicenseReport",
+    version: 1,
+    producer: "pkgrank analyze --licenses --out",
+};
+
+pub const OWNERS: Artifact = Artifact {
+    name: "owners",
+    file: "owners.json",
+    schema: "Vec<OwnerRollup>",
+    version: 1,
+    producer: "pkgrank owners --out",
+};
+
+/// The foundation ranking kept apart from `analyze.json`, which the next
+/// run with another metric overwrites.
+pub const FOUNDATION: Artifact = Artifact {
+    name: "foundation",
+    file: "foundation.json",
+    schema: "AnalyzeOut",
+    version: 1,
+    producer: "pkgrank analyze --metric foundation --out",
+};
+
+pub const ALL: &[Artifact] = &[
+    ANALYZE,
+    WORKSPACE_GRAPH,
+    MODULES,
+    DIGEST_STATE,
+    SNAPSHOT,
+    LICENSES,
+    OWNERS,
+    FOUNDATION,
+];
+
+impl Artifact {
+    pub fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(self.file)
+    }
+
+    /// Write `value` in a provenance envelope to this artifact's path under
+    /// `dir`. `root` is the workspace the data was computed from.
+    pub fn write<T: Serialize>(
+        &self,
+        dir: &Path,
+        root: Option<&Path>,
+        value: &T,
+    ) -> anyhow::Result<PathBuf> {
+        let path = self.path(dir);
+        self.write_to(&path, root, value)?;
+        Ok(path)
+    }
+
+    /// Like `write`, for artifacts whose location the user chose.
+    pub fn write_to<T: Serialize>(
+        &self,
+        path: &Path,
+        root: Option<&Path>,
+        value: &T,
+    ) -> anyhow::Result<()> {
+        write_json(
+            path,
+            &Envelope {
+                provenance: Provenance::new(self, root),
+                data: value,
+            },
+        )
+    }
+}
+
+/// How every registered artifact is stored: the payload under `data`, next
+/// to a record of the run that produced it.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Envelope<T> {
+    pub provenance: Provenance,
+    pub data: T,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Provenance {
+    pub artifact: String,
+    /// The artifact's `version` when written.
+    pub schema_version: u32,
+    pub pkgrank_version: String,
+    pub generated_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    /// Command-line arguments of the producing run, without the program name
+    /// (lossily converted when not UTF-8).
+    pub args: Vec<String>,
+    /// Content hashes (see `content_hash`) of input files under `root`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inputs: BTreeMap<String, String>,
+}
+
+/// Input files whose hash is recorded, relative to the workspace root.
+const INPUTS: &[&str] = &["Cargo.lock"];
+
+impl Provenance {
+    fn new(artifact: &Artifact, root: Option<&Path>) -> Self {
+        Provenance {
+            artifact: artifact.name.to_string(),
+            schema_version: artifact.version,
+            pkgrank_version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: time::now_secs(),
+            root: root.map(|r| r.display().to_string()),
+            args: std::env::args_os()
+                .skip(1)
+                .map(|a| a.to_string_lossy().into_owned())
+                .collect(),
+            inputs: root
+                .into_iter()
+                .flat_map(|root| {
+                    INPUTS.iter().filter_map(move |file| {
+                        let bytes = std::fs::read(root.join(file)).ok()?;
+                        Some((file.to_string(), content_hash(&bytes)))
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Read an artifact's payload, with or without the provenance envelope
+/// (files from before version 2 have none).
+pub fn read<T: DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored<T> {
+        Wrapped(Envelope<T>),
+        Bare(T),
+    }
+    let bytes = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    let stored =
+        serde_json::from_slice(&bytes).with_context(|| format!("parsing {}", path.display()))?;
+    Ok(match stored {
+        Stored::Wrapped(envelope) => envelope.data,
+        Stored::Bare(value) => value,
+    })
+}
+
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(value)?)
+        .with_context(|| format!("writing {}", path.display()))
+}
+
+/// 64-bit FNV-1a of `bytes` as 16 hex digits. Detects changed files; not
+/// meant to resist tampering.
+pub fn content_hash(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |h, &b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ArtifactRow {
+    #[serde(flatten)]
+    pub artifact: Artifact,
+    /// Size in bytes when present in the listed directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+}
+
+/// Every known artifact, with on-disk size if `dir` is given and the file exists.
+pub fn list(dir: Option<&Path>) -> Vec<ArtifactRow> {
+    ALL.iter()
+        .map(|&artifact| ArtifactRow {
+            artifact,
+            bytes: dir
+                .and_then(|d| std::fs::metadata(artifact.path(d)).ok())
+                .map(|m| m.len()),
+        })
+        .collect()
+}
+
+pub fn print_text(rows: &[ArtifactRow], dir: Option<&Path>) {
+    match dir {
+        Some(dir) => println!("Artifacts in {}:", dir.display()),
+        None => println!("Artifacts:"),
+    }
+    println!("{:─<50}", "");
+    for row in rows {
+        let a = &row.artifact;
+        let present = match (dir, row.bytes) {
+            (None, _) => String::new(),
+            (Some(_), Some(bytes)) => format!("  {} bytes", bytes),
+            (Some(_), None) => "  missing".to_string(),
+        };
+        println!(
+            "{:16} {:21} {} v{}  ({}){}",
+            a.name, a.file, a.schema, a.version, a.producer, present
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Centrality metrics over directed graphs.
+//!
+//! Each metric returns one score per node, indexed by `NodeIndex::index()`.
+
+use petgraph::prelude::*;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::cmp::Ordering;
+
+/// Edge weight used by the weighted metrics (PageRank).
+pub trait Weighted {
+    fn weight(&self) -> f64;
+}
+
+impl Weighted for () {
+    fn weight(&self) -> f64 {
+        1.0
+    }
+}
+
+/// PageRank iteration settings.
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema)]
+pub struct PageRankConfig {
+    pub damping: f64,
+    pub max_iter: usize,
+    pub tolerance: f64,
+}
+
+impl Default for PageRankConfig {
+    fn default() -> Self {
+        Self {
+            damping: 0.85,
+            max_iter: 100,
+            tolerance: 1e-8,
+        }
+    }
+}
+
+/// How a PageRank run ended.
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema)]
+pub struct Convergence {
+    pub iterations: usize,
+    /// L1 distance between the last two iterates.
+    pub final_diff: f64,
+    pub converged: bool,
+}
+
+pub fn pagerank<N, E: Weighted>(
+    graph: &DiGraph<N, E>,
+    config: &PageRankConfig,
+) -> (Vec<f64>, Convergence) {
+    pagerank_observed(graph, config, &mut |_, _| {})
+}
+
+/// [`pagerank`], calling `observe(iteration, diff)` after every iteration.
+pub fn pagerank_observed<N, E: Weighted>(
+    graph: &DiGraph<N, E>,
+    config: &PageRankConfig,
+    observe: &mut dyn FnMut(usize, f64),
+) -> (Vec<f64>, Convergence) {
+    let n = graph.node_count();
+    if n == 0 {
+        return (vec![], Convergence::empty());
+    }
+    pagerank_with_teleport(graph, &vec![1.0 / n as f64; n], config, observe)
+}
+
+/// PageRank whose random jumps land only on `seeds` ("what do these lean on"),
+/// calling `observe(iteration, diff)` after every iteration.
+pub fn personalized_pagerank_observed<N, E: Weighted>(
+    graph: &DiGraph<N, E>,
+    seeds: &[NodeIndex],
+    config: &PageRankConfig,
+    observe: &mut dyn FnMut(usize, f64),
+) -> (Vec<f64>, Convergence) {
+    let n = graph.node_count();
+    if n == 0 || seeds.is_empty() {
+        return (vec![0.0; n], Convergence::empty());
+    }
+    let mut teleport = vec![0.0; n];
+    for seed in seeds {
+        teleport[seed.index()] += 1.0 / seeds.len() as f64;
+    }
+    pagerank_with_teleport(graph, &teleport, config, observe)
+}
+
+impl Convergence {
+    fn empty() -> Self {
+        Self {
+            iterations: 0,
+            final_diff: 0.0,
+            converged: true,
+        }
+    }
+}
+
+fn pagerank_with_teleport<N, E: Weighted>(
+    graph: &DiGraph<N, E>,
+    teleport: &[f64],
+    config: &PageRankConfig,
+    observe: &mut dyn FnMut(usize, f64),
+) -> (Vec<f64>, Convergence) {
+    let n = graph.node_count();
+    let damping = config.damping;
+    let mut scores: Vec<f64> = teleport.to_vec();
+    let mut new_scores = vec![0.0; n];
+    let mut out_weight = vec![0.0; n];
+    for edge in graph.edge_references() {
+        out_weight[edge.source().index()] += edge.weight().weight();
+    }
+    let mut convergence = Convergence {
+        iterations: 0,
+        final_diff: f64::INFINITY,
+        converged: false,
+    };
+
+    for _ in 0..config.max_iter {
+        let mut diff = 0.0;
+        for node in graph.node_indices() {
+            let mut sum = 0.0;
+            for edge in graph.edges_directed(node, Direction::Incoming) {
+                let src = edge.source().index();
+                if out_weight[src] > 0.0 {
+                    sum += scores[src] * edge.weight().weight() / out_weight[src];
+                }
+            }
+            new_scores[node.index()] = (1.0 - damping) * teleport[node.index()] + damping * sum;
+            diff += (new_scores[node.index()] - scores[node.index()]).abs();
+        }
+        std::mem::swap(&mut scores, &mut new_scores);
+        convergence.iterations += 1;
+        convergence.final_diff = diff;
+        observe(convergence.iterations, diff);
+        if diff < config.tolerance {
+            convergence.converged = true;
+            break;
+        }
+    }
+
+    (scores, convergence)
+}
+
+/// Order by descending score. Callers chain a tie-break on name and
+/// version so equal scores come out in the same order on every run.
+pub fn by_score_desc(a: f64, b: f64) -> Ordering {
+    b.total_cmp(&a)
+}
+
+pub fn degree_centrality<N, E>(graph: &DiGraph<N, E>, dir: Direction) -> Vec<f64> {
+    let n = graph.node_count() as f64;
+    if n <= 1.0 {
+        return vec![0.0; graph.node_count()];
+    }
+    graph
+        .node_indices()
+        .map(|i| graph.neighbors_directed(i, dir).count() as f64 / (n - 1.0))
+        .collect()
+}
+
+pub fn betweenness_centrality<N, E>(graph: &DiGraph<N, E>) -> Vec<f64> {
+    let n = graph.node_count();
+    if n <= 2 {
+        return vec![0.0; n];
+    }
+
+    let mut betweenness = vec![0.0; n];
+
+    for s in graph.node_indices() {
+        let mut stack = Vec::new();
+        let mut pred: Vec<Vec<NodeIndex>> = vec![vec![]; n];
+        let mut sigma = vec![0.0; n];
+        let mut dist: Vec<i32> = vec![-1; n];
+
+        sigma[s.index()] = 1.0;
+        dist[s.index()] = 0;
+
+        let mut queue = std::collections::VecDeque::new();
+        queue.push_back(s);
+
+        while let Some(v) = queue.pop_front() {
+            stack.push(v);
+            for w in graph.neighbors_directed(v, Direction::Outgoing) {
+                if dist[w.index()] < 0 {
+                    dist[w.index()] = dist[v.index()] + 1;
+                    queue.push_back(w);
+                }
+                if dist[w.index()] == dist[v.index()] + 1 {
+                    sigma[w.index()] += sigma[v.index()];
+                    pred[w.index()].push(v);
+                }
+            }
+        }
+
+        let mut delta = vec![0.0; n];
+        while let Some(w) = stack.pop() {
+            for &v in &pred[w.index()] {
+                delta[v.index()] +=
+                    (sigma[v.index()] / sigma[w.index()]) * (1.0 + delta[w.index()]);
+            }
+            if w != s {
+                betweenness[w.index()] += delta[w.index()];
+            }
+        }
+    }
+
+    let norm = 2.0 / ((n - 1) * (n - 2)) as f64;
+    betweenness.iter().map(|b| b * norm).collect()
+}
+
+/// k-core number of each node, ignoring edge direction, self-loops and
+/// parallel edges: the largest k such that the node is in a subgraph where
+/// every node has at least k neighbors.
+pub fn coreness<N, E>(graph: &DiGraph<N, E>) -> Vec<f64> {
+    let n = graph.node_count();
+    let neighbors: Vec<Vec<usize>> = graph
+        .node_indices()
+        .map(|i| {
+            let mut ns: Vec<usize> = graph
+                .neighbors_undirected(i)
+                .filter(|&j| j != i)
+                .map(|j| j.index())
+                .collect();
+            ns.sort_unstable();
+            ns.dedup();
+            ns
+        })
+        .collect();
+    let mut degree: Vec<usize> = neighbors.iter().map(Vec::len).collect();
+    let mut core = vec![0.0; n];
+    let mut removed = vec![false; n];
+    let mut k = 0;
+    for _ in 0..n {
+        let v = (0..n)
+            .filter(|&v| !removed[v])
+            .min_by_key(|&v| (degree[v], v))
+            .expect("a node is left each round");
+        k = k.max(degree[v]);
+        core[v] = k as f64;
+        removed[v] = true;
+        for &w in &neighbors[v] {
+            if !removed[w] {
+                degree[w] -= 1;
+            }
+        }
+    }
+    core
+}
@@ -0,0 +1,138 @@
+//! `pkgrank co-change`: crates that change together in git history,
+//! compared against the dependency graph.
+
+use crate::git;
+use crate::graph::DepGraph;
+use petgraph::prelude::*;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CoChangePair {
+    pub a: String,
+    pub b: String,
+    /// Commits touching both crates.
+    pub commits: usize,
+    /// `commits` over the commits touching either crate.
+    pub coupling: f64,
+    /// Direct dependency between the two, as `"a -> b"` / `"b -> a"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependency: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CoChangeOut {
+    pub window_days: u64,
+    /// Commits considered (touching at least one crate, under the size cap).
+    pub commits: usize,
+    /// By descending co-change count; pairs without a dependency are hidden coupling.
+    pub pairs: Vec<CoChangePair>,
+}
+
+pub fn co_change(
+    graph: &DepGraph,
+    workspace_root: &Path,
+    days: u64,
+    min_commits: usize,
+    max_files: usize,
+) -> CoChangeOut {
+    // Longest directory first so nested crates claim their own files.
+    let mut dirs: Vec<(PathBuf, NodeIndex)> = graph
+        .node_indices()
+        .filter(|&i| graph[i].workspace_member)
+        .filter_map(|i| Some((graph[i].manifest_path.parent()?.to_path_buf(), i)))
+        .collect();
+    dirs.sort_by(|(a, _), (b, _)| {
+        b.components()
+            .count()
+            .cmp(&a.components().count())
+            .then_with(|| a.cmp(b))
+    });
+    let owner = |file: &Path| {
+        dirs.iter()
+            .find(|(d, _)| file.starts_with(d))
+            .map(|&(_, i)| i)
+    };
+
+    let mut touched: BTreeMap<NodeIndex, usize> = BTreeMap::new();
+    let mut together: BTreeMap<(NodeIndex, NodeIndex), usize> = BTreeMap::new();
+    let mut commits = 0;
+    for files in git::commit_files(workspace_root, days).unwrap_or_default() {
+        // Sweeping commits (formatting, renames, version bumps) couple everything.
+        if files.len() > max_files {
+            continue;
+        }
+        let crates: BTreeSet<NodeIndex> = files.iter().filter_map(|f| owner(f)).collect();
+        if crates.is_empty() {
+            continue;
+        }
+        commits += 1;
+        for &c in &crates {
+            *touched.entry(c).or_default() += 1;
+        }
+        for &a in &crates {
+            for &b in crates.range(a..).skip(1) {
+                *together.entry((a, b)).or_default() += 1;
+            }
+        }
+    }
+
+    let mut pairs: Vec<CoChangePair> = together
+        .into_iter()
+        .filter(|&(_, n)| n >= min_commits)
+        .map(|((a, b), n)| {
+            let (na, nb) = (&graph[a].name, &graph[b].name);
+            let dependency = if graph.contains_edge(a, b) {
+                Some(format!("{} -> {}", na, nb))
+            } else if graph.contains_edge(b, a) {
+                Some(format!("{} -> {}", nb, na))
+            } else {
+                None
+            };
+            CoChangePair {
+                a: na.clone(),
+                b: nb.clone(),
+                commits: n,
+                coupling: n as f64 / (touched[&a] + touched[&b] - n) as f64,
+                dependency,
+            }
+        })
+        .collect();
+    pairs.sort_by(|x, y| {
+        y.commits
+            .cmp(&x.commits)
+            .then_with(|| (&x.a, &x.b).cmp(&(&y.a, &y.b)))
+    });
+
+    CoChangeOut {
+        window_days: days,
+        commits,
+        pairs,
+    }
+}
+
+pub fn print_text(out: &CoChangeOut, top: usize) {
+    println!(
+        "Crates changed together ({} commits in the last {} days):",
+        out.commits, out.window_days
+    );
+    println!("{:─<50}", "");
+    for (i, pair) in out.pairs.iter().take(top).enumerate() {
+        let label = format!("{} + {}", pair.a, pair.b);
+        let dependency = pair.dependency.as_deref().unwrap_or("no dependency edge");
+        println!(
+            "{:3}. {:40} {:4} commits  {:.2}  {}",
+            i + 1,
+            label,
+            pair.commits,
+            pair.coupling,
+            dependency
+        );
+    }
+    let hidden = out.pairs.iter().filter(|p| p.dependency.is_none()).count();
+    if hidden > 0 {
+        println!("\n{} coupled pairs have no direct dependency edge", hidden);
+    }
+}
@@ -0,0 +1,188 @@
+//! CODEOWNERS parsing and crate owner assignment.
+//!
+//! Owners are resolved for each package's `Cargo.toml`, using GitHub's
+//! rules: gitignore-style patterns, last matching line wins.
+
+use crate::git;
+use crate::glob::match_segments;
+use crate::graph::DepGraph;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+/// Locations checked (relative to the repository root) when no file is given.
+const DEFAULT_LOCATIONS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+#[derive(Debug)]
+struct Rule {
+    /// Pattern split on `/`; an anchored pattern starts at the root.
+    segments: Vec<String>,
+    anchored: bool,
+    /// Owners as written (`@org/team`, `@user`, `user@example.com`).
+    owners: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct CodeOwners {
+    root: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl CodeOwners {
+    /// Load `path`, or the first CODEOWNERS file found in the default
+    /// locations under the repository containing `workspace_root`.
+    pub fn discover(workspace_root: &Path, path: Option<&Path>) -> anyhow::Result<Option<Self>> {
+        let root = git::toplevel(workspace_root).unwrap_or_else(|| workspace_root.to_path_buf());
+        let file = match path {
+            Some(p) => p.to_path_buf(),
+            None => match DEFAULT_LOCATIONS
+                .iter()
+                .map(|loc| root.join(loc))
+                .find(|p| p.is_file())
+            {
+                Some(p) => p,
+                None => return Ok(None),
+            },
+        };
+        let text = std::fs::read_to_string(&file)
+            .with_context(|| format!("failed to read {}", file.display()))?;
+        Ok(Some(Self::parse(root, &text)))
+    }
+
+    fn parse(root: PathBuf, text: &str) -> Self {
+        let rules = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let pattern = parts.next()?;
+                let owners: Vec<String> = parts
+                    .take_while(|p| !p.starts_with('#'))
+                    .map(str::to_string)
+                    .collect();
+                let anchored =
+                    pattern.starts_with('/') || pattern.trim_end_matches('/').contains('/');
+                let mut segments: Vec<String> = pattern
+                    .trim_start_matches('/')
+                    .split('/')
+                    .map(str::to_string)
+                    .collect();
+                // `dir/` owns everything below it.
+                if segments.last().is_some_and(|s| s.is_empty()) {
+                    *segments.last_mut().unwrap() = "**".to_string();
+                }
+                Some(Rule {
+                    segments,
+                    anchored,
+                    owners,
+                })
+            })
+            .collect();
+        Self { root, rules }
+    }
+
+    /// Owners of `path`, or `None` if no rule matches or the path lies
+    /// outside the repository.
+    pub fn owners_of(&self, path: &Path) -> Option<&[String]> {
+        let rel = path.strip_prefix(&self.root).ok()?;
+        let segments: Vec<&str> = rel.iter().filter_map(|s| s.to_str()).collect();
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(&segments))
+            .map(|rule| rule.owners.as_slice())
+            .filter(|owners| !owners.is_empty())
+    }
+}
+
+impl Rule {
+    fn matches(&self, path: &[&str]) -> bool {
+        let pattern: Vec<&str> = self.segments.iter().map(String::as_str).collect();
+        // `docs/*` owns direct children only; other patterns naming a
+        // directory own everything below it, so ancestors are tried too.
+        let children_only = pattern.last().is_some_and(|s| s.contains('*'));
+        (1..=path.len()).any(|end| {
+            if end < path.len() && children_only {
+                return false;
+            }
+            let prefix = &path[..end];
+            if self.anchored {
+                match_segments(&pattern, prefix)
+            } else {
+                (0..end).any(|start| match_segments(&pattern, &prefix[start..]))
+            }
+        })
+    }
+}
+
+/// Set `owner` on every package whose manifest is covered by a rule.
+pub fn assign_owners(graph: &mut DepGraph, owners: &CodeOwners) {
+    for i in graph.node_indices() {
+        let owner = owners
+            .owners_of(&graph[i].manifest_path)
+            .map(|o| o.join(" "));
+        graph[i].owner = owner;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owners(text: &str, path: &str) -> Option<String> {
+        CodeOwners::parse(PathBuf::from("/repo"), text)
+            .owners_of(&Path::new("/repo").join(path))
+            .map(|o| o.join(" "))
+    }
+
+    #[test]
+    fn last_matching_rule_wins() {
+        let text = "* @all\ncrates/ @crates-team\n/crates/net/ @net @sre # on call\n";
+        assert_eq!(owners(text, "Cargo.toml").as_deref(), Some("@all"));
+        assert_eq!(
+            owners(text, "crates/core/Cargo.toml").as_deref(),
+            Some("@crates-team")
+        );
+        assert_eq!(
+            owners(text, "crates/net/Cargo.toml").as_deref(),
+            Some("@net @sre")
+        );
+        // Reversed, the broad rule shadows the specific one.
+        let reversed = "/crates/net/ @net\n* @all\n";
+        assert_eq!(
+            owners(reversed, "crates/net/Cargo.toml").as_deref(),
+            Some("@all")
+        );
+    }
+
+    #[test]
+    fn a_later_rule_without_owners_unsets_ownership() {
+        let text = "* @all\nvendor/\n";
+        assert_eq!(owners(text, "vendor/zlib/Cargo.toml"), None);
+    }
+
+    #[test]
+    fn anchoring_and_direct_children() {
+        let text = "/tools/ @tools\nbench/ @perf\ndocs/* @docs\n";
+        assert_eq!(
+            owners(text, "tools/gen/Cargo.toml").as_deref(),
+            Some("@tools")
+        );
+        assert_eq!(owners(text, "crates/tools/Cargo.toml"), None);
+        assert_eq!(
+            owners(text, "crates/x/bench/Cargo.toml").as_deref(),
+            Some("@perf")
+        );
+        assert_eq!(owners(text, "docs/Cargo.toml").as_deref(), Some("@docs"));
+        assert_eq!(owners(text, "docs/book/Cargo.toml"), None);
+    }
+
+    #[test]
+    fn paths_outside_the_repository_have_no_owner() {
+        assert_eq!(
+            CodeOwners::parse(PathBuf::from("/repo"), "* @all")
+                .owners_of(Path::new("/elsewhere/Cargo.toml")),
+            None
+        );
+    }
+}
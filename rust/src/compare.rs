@@ -0,0 +1,372 @@
+//! `pkgrank compare-runs`: what changed between two artifact directories
+//! (snapshots or `--out` dirs).
+
+use crate::analyze::WorkspaceGraph;
+use crate::artifacts::{self, Artifact};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CompareOut {
+    pub old: String,
+    pub new: String,
+    /// Rank moves smaller than this were left out.
+    pub min_move: usize,
+    /// From `analyze.json`, if both runs have it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crates: Option<RankDiff>,
+    /// From `foundation.json`, if both runs have it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foundation: Option<RankDiff>,
+    /// From `workspace_graph.json`, if both runs have it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edges: Option<EdgeDiff>,
+    /// From `modules.json`, if both runs have it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modules: Option<RankDiff>,
+    /// Module cycles in the new run that the old run did not have (needs
+    /// `modules --report-cycles` in both), each as its sorted members.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub new_cycles: Vec<Vec<String>>,
+    /// Artifacts present in only one of the runs, so not compared.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unmatched: Vec<String>,
+}
+
+/// Changes in a ranking. Rankings are truncated to the producing run's
+/// `--top`, so "entered" and "left" are relative to that cut-off.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RankDiff {
+    pub entered: Vec<String>,
+    pub left: Vec<String>,
+    /// Largest moves first.
+    pub moved: Vec<RankMove>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RankMove {
+    pub name: String,
+    pub old_rank: usize,
+    pub new_rank: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct EdgeDiff {
+    /// `[dependent, dependency]`, sorted.
+    pub added: Vec<[String; 2]>,
+    pub removed: Vec<[String; 2]>,
+}
+
+/// The parts of `analyze.json` and `modules.json` compared here.
+#[derive(Deserialize)]
+struct Ranked {
+    rows: Vec<RankedRow>,
+    #[serde(default)]
+    cycles: Vec<Cycle>,
+}
+
+#[derive(Deserialize)]
+struct RankedRow {
+    rank: usize,
+    #[serde(alias = "module")]
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Cycle {
+    modules: Vec<String>,
+}
+
+pub fn compare(old: &Path, new: &Path, min_move: usize) -> anyhow::Result<CompareOut> {
+    let mut unmatched = Vec::new();
+    let mut both = |artifact: Artifact| -> Option<(PathBuf, PathBuf)> {
+        let (a, b) = (artifact.path(old), artifact.path(new));
+        match (a.exists(), b.exists()) {
+            (true, true) => Some((a, b)),
+            (false, false) => None,
+            _ => {
+                unmatched.push(artifact.file.to_string());
+                None
+            }
+        }
+    };
+
+    let crates = both(artifacts::ANALYZE)
+        .map(|(a, b)| -> anyhow::Result<RankDiff> {
+            let (a, b): (Ranked, Ranked) = (artifacts::read(&a)?, artifacts::read(&b)?);
+            Ok(rank_diff(&a.rows, &b.rows, min_move))
+        })
+        .transpose()?;
+
+    let foundation = both(artifacts::FOUNDATION)
+        .map(|(a, b)| -> anyhow::Result<RankDiff> {
+            let (a, b): (Ranked, Ranked) = (artifacts::read(&a)?, artifacts::read(&b)?);
+            Ok(rank_diff(&a.rows, &b.rows, min_move))
+        })
+        .transpose()?;
+
+    let edges = both(artifacts::WORKSPACE_GRAPH)
+        .map(|(a, b)| -> anyhow::Result<EdgeDiff> {
+            let (a, b): (WorkspaceGraph, WorkspaceGraph) =
+                (artifacts::read(&a)?, artifacts::read(&b)?);
+            let (a, b): (BTreeSet<_>, BTreeSet<_>) =
+                (a.edges.into_iter().collect(), b.edges.into_iter().collect());
+            Ok(EdgeDiff {
+                added: b.difference(&a).cloned().collect(),
+                removed: a.difference(&b).cloned().collect(),
+            })
+        })
+        .transpose()?;
+
+    let mut new_cycles = Vec::new();
+    let modules = both(artifacts::MODULES)
+        .map(|(a, b)| -> anyhow::Result<RankDiff> {
+            let (a, b): (Ranked, Ranked) = (artifacts::read(&a)?, artifacts::read(&b)?);
+            let key = |c: &Cycle| c.modules.iter().cloned().collect::<BTreeSet<String>>();
+            let known: BTreeSet<BTreeSet<String>> = a.cycles.iter().map(key).collect();
+            new_cycles = b
+                .cycles
+                .iter()
+                .filter(|c| !known.contains(&key(c)))
+                .map(|c| c.modules.clone())
+                .collect();
+            Ok(rank_diff(&a.rows, &b.rows, min_move))
+        })
+        .transpose()?;
+
+    Ok(CompareOut {
+        old: old.display().to_string(),
+        new: new.display().to_string(),
+        min_move,
+        crates,
+        foundation,
+        edges,
+        modules,
+        new_cycles,
+        unmatched,
+    })
+}
+
+/// A name's best rank wins when it appears more than once (several versions
+/// of one package).
+fn rank_diff(old: &[RankedRow], new: &[RankedRow], min_move: usize) -> RankDiff {
+    let ranks = |rows: &[RankedRow]| {
+        let mut m: BTreeMap<String, usize> = BTreeMap::new();
+        for r in rows {
+            let rank = m.entry(r.name.clone()).or_insert(r.rank);
+            *rank = (*rank).min(r.rank);
+        }
+        m
+    };
+    let (old, new) = (ranks(old), ranks(new));
+
+    let mut moved: Vec<RankMove> = new
+        .iter()
+        .filter_map(|(name, &new_rank)| {
+            let &old_rank = old.get(name)?;
+            (old_rank.abs_diff(new_rank) >= min_move.max(1)).then(|| RankMove {
+                name: name.clone(),
+                old_rank,
+                new_rank,
+            })
+        })
+        .collect();
+    moved.sort_by(|a, b| {
+        b.old_rank
+            .abs_diff(b.new_rank)
+            .cmp(&a.old_rank.abs_diff(a.new_rank))
+            .then_with(|| a.new_rank.cmp(&b.new_rank))
+    });
+
+    RankDiff {
+        entered: new
+            .keys()
+            .filter(|k| !old.contains_key(*k))
+            .cloned()
+            .collect(),
+        left: old
+            .keys()
+            .filter(|k| !new.contains_key(*k))
+            .cloned()
+            .collect(),
+        moved,
+    }
+}
+
+fn movement(m: &RankMove) -> String {
+    if m.new_rank < m.old_rank {
+        format!("↑{}", m.old_rank - m.new_rank)
+    } else {
+        format!("↓{}", m.new_rank - m.old_rank)
+    }
+}
+
+pub fn render_text(out: &CompareOut) -> String {
+    let mut s = String::new();
+    writeln!(s, "Comparing {} -> {}", out.old, out.new).unwrap();
+    writeln!(s, "{:─<50}", "").unwrap();
+    for (title, diff) in [
+        ("Crates", &out.crates),
+        ("Foundation", &out.foundation),
+        ("Modules", &out.modules),
+    ] {
+        let Some(diff) = diff else { continue };
+        writeln!(s, "{}:", title).unwrap();
+        for m in &diff.moved {
+            let line = format!(
+                "  {:40} {:4} -> {:4} {}",
+                m.name,
+                m.old_rank,
+                m.new_rank,
+                movement(m)
+            );
+            writeln!(s, "{}", line.trim_end()).unwrap();
+        }
+        for (label, items) in [("entered", &diff.entered), ("left", &diff.left)] {
+            if !items.is_empty() {
+                writeln!(s, "  {}: {}", label, items.join(", ")).unwrap();
+            }
+        }
+        if diff.moved.is_empty() && diff.entered.is_empty() && diff.left.is_empty() {
+            writeln!(s, "  no changes").unwrap();
+        }
+    }
+    if let Some(edges) = &out.edges {
+        writeln!(s, "Workspace edges:").unwrap();
+        for [from, to] in &edges.added {
+            writeln!(s, "  + {} -> {}", from, to).unwrap();
+        }
+        for [from, to] in &edges.removed {
+            writeln!(s, "  - {} -> {}", from, to).unwrap();
+        }
+        if edges.added.is_empty() && edges.removed.is_empty() {
+            writeln!(s, "  no changes").unwrap();
+        }
+    }
+    // Sorted members of a strongly connected group, not a path: no arrows.
+    for cycle in &out.new_cycles {
+        writeln!(s, "New cycle: {{{}}}", cycle.join(", ")).unwrap();
+    }
+    if !out.unmatched.is_empty() {
+        writeln!(s, "Only in one run: {}", out.unmatched.join(", ")).unwrap();
+    }
+    s
+}
+
+pub fn render_markdown(out: &CompareOut) -> String {
+    let mut s = String::new();
+    writeln!(s, "## pkgrank: `{}` → `{}`", out.old, out.new).unwrap();
+    for (title, diff) in [
+        ("Crates", &out.crates),
+        ("Foundation", &out.foundation),
+        ("Modules", &out.modules),
+    ] {
+        let Some(diff) = diff else { continue };
+        writeln!(s, "\n### {}\n", title).unwrap();
+        if !diff.moved.is_empty() {
+            writeln!(s, "| Name | Old rank | New rank | Change |").unwrap();
+            writeln!(s, "|---|---|---|---|").unwrap();
+            for m in &diff.moved {
+                writeln!(
+                    s,
+                    "| `{}` | {} | {} | {} |",
+                    m.name,
+                    m.old_rank,
+                    m.new_rank,
+                    movement(m)
+                )
+                .unwrap();
+            }
+        }
+        for (label, items) in [("Entered", &diff.entered), ("Left", &diff.left)] {
+            if !items.is_empty() {
+                let items: Vec<String> = items.iter().map(|i| format!("`{}`", i)).collect();
+                writeln!(s, "\n{}: {}", label, items.join(", ")).unwrap();
+            }
+        }
+        if diff.moved.is_empty() && diff.entered.is_empty() && diff.left.is_empty() {
+            writeln!(s, "No changes.").unwrap();
+        }
+    }
+    if let Some(edges) = &out.edges {
+        writeln!(s, "\n### Workspace edges\n").unwrap();
+        for [from, to] in &edges.added {
+            writeln!(s, "- added `{}` → `{}`", from, to).unwrap();
+        }
+        for [from, to] in &edges.removed {
+            writeln!(s, "- removed `{}` → `{}`", from, to).unwrap();
+        }
+        if edges.added.is_empty() && edges.removed.is_empty() {
+            writeln!(s, "No changes.").unwrap();
+        }
+    }
+    if !out.new_cycles.is_empty() {
+        writeln!(s, "\n### New module cycles\n").unwrap();
+        for cycle in &out.new_cycles {
+            writeln!(s, "- {{`{}`}}", cycle.join("`, `")).unwrap();
+        }
+    }
+    if !out.unmatched.is_empty() {
+        writeln!(s, "\nOnly in one run: {}", out.unmatched.join(", ")).unwrap();
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(names: &[&str]) -> Vec<RankedRow> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| RankedRow {
+                rank: i + 1,
+                name: name.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn min_move_filters_small_moves() {
+        let old = rows(&["a", "b", "c", "d", "e"]);
+        let new = rows(&["b", "a", "e", "c", "d"]);
+
+        let all = rank_diff(&old, &new, 0);
+        let moved: Vec<(&str, usize, usize)> = all
+            .moved
+            .iter()
+            .map(|m| (m.name.as_str(), m.old_rank, m.new_rank))
+            .collect();
+        // Largest move first, ties by new rank.
+        assert_eq!(
+            moved,
+            [
+                ("e", 5, 3),
+                ("b", 2, 1),
+                ("a", 1, 2),
+                ("c", 3, 4),
+                ("d", 4, 5)
+            ]
+        );
+
+        let big = rank_diff(&old, &new, 2);
+        assert_eq!(big.moved.len(), 1);
+        assert_eq!(big.moved[0].name, "e");
+        assert!(rank_diff(&old, &new, 3).moved.is_empty());
+    }
+
+    #[test]
+    fn entered_and_left_use_the_best_rank_per_name() {
+        let old = rows(&["a", "b", "a"]);
+        let new = rows(&["c", "a"]);
+        let diff = rank_diff(&old, &new, 1);
+        assert_eq!(diff.entered, ["c"]);
+        assert_eq!(diff.left, ["b"]);
+        assert_eq!(diff.moved.len(), 1);
+        assert_eq!((diff.moved[0].old_rank, diff.moved[0].new_rank), (1, 2));
+    }
+}
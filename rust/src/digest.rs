@@ -0,0 +1,385 @@
+//! `pkgrank digest`: a compact, cron-friendly ranking report with deltas
+//! against the previous digest.
+
+use crate::artifacts;
+use crate::centrality::{self, PageRankConfig};
+use crate::graph::DepGraph;
+use crate::time;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::Path;
+
+/// What a digest run records as its high-water mark.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DigestState {
+    pub generated_at: u64,
+    pub nodes: usize,
+    pub edges: usize,
+    /// Every package, by descending pagerank.
+    pub packages: Vec<DigestRow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DigestRow {
+    pub name: String,
+    pub version: String,
+    pub score: f64,
+    pub workspace_member: bool,
+    /// Manifest directory of path packages; identifies a crate across renames.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct DigestDelta {
+    pub since: u64,
+    pub nodes: i64,
+    pub edges: i64,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// `(name, old version, new version)`
+    pub version_changes: Vec<(String, String, String)>,
+    /// `(old name, new name)` for path packages whose directory kept its crate.
+    pub renames: Vec<(String, String)>,
+    /// Previous rank by package name (under the new name for renames).
+    pub previous_rank: BTreeMap<String, usize>,
+}
+
+pub fn compute(graph: &DepGraph, config: &PageRankConfig) -> DigestState {
+    let (scores, _) = centrality::pagerank(graph, config);
+    let mut packages: Vec<DigestRow> = graph
+        .node_indices()
+        .map(|i| DigestRow {
+            name: graph[i].name.clone(),
+            version: graph[i].version.clone(),
+            score: scores[i.index()],
+            workspace_member: graph[i].workspace_member,
+            dir: graph[i]
+                .source
+                .is_none()
+                .then(|| graph[i].manifest_path.parent())
+                .flatten()
+                .map(|d| d.display().to_string()),
+        })
+        .collect();
+    packages.sort_by(|a, b| {
+        centrality::by_score_desc(a.score, b.score)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.version.cmp(&b.version))
+    });
+
+    DigestState {
+        generated_at: time::now_secs(),
+        nodes: graph.node_count(),
+        edges: graph.edge_count(),
+        packages,
+    }
+}
+
+pub fn load_state(path: &Path) -> anyhow::Result<Option<DigestState>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    artifacts::read(path).map(Some)
+}
+
+pub fn save_state(path: &Path, root: &Path, state: &DigestState) -> anyhow::Result<()> {
+    artifacts::DIGEST_STATE.write_to(path, Some(root), state)
+}
+
+pub fn diff(prev: &DigestState, cur: &DigestState) -> DigestDelta {
+    let key = |r: &DigestRow| format!("{} {}", r.name, r.version);
+    let prev_keys: BTreeSet<String> = prev.packages.iter().map(key).collect();
+    let cur_keys: BTreeSet<String> = cur.packages.iter().map(key).collect();
+
+    let versions = |state: &DigestState| {
+        let mut m: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for r in &state.packages {
+            m.entry(r.name.clone())
+                .or_default()
+                .insert(r.version.clone());
+        }
+        m
+    };
+    let prev_versions = versions(prev);
+    let cur_versions = versions(cur);
+
+    // A package whose only version changed is reported as an upgrade, not add + remove.
+    let mut version_changes = Vec::new();
+    let mut moved: BTreeSet<String> = BTreeSet::new();
+    for (name, old) in &prev_versions {
+        if let Some(new) = cur_versions.get(name)
+            && old.len() == 1
+            && new.len() == 1
+            && old != new
+        {
+            let (old, new) = (old.first().unwrap(), new.first().unwrap());
+            version_changes.push((name.clone(), old.clone(), new.clone()));
+            moved.insert(format!("{} {}", name, old));
+            moved.insert(format!("{} {}", name, new));
+        }
+    }
+
+    let mut added: Vec<String> = cur_keys
+        .difference(&prev_keys)
+        .filter(|k| !moved.contains(*k))
+        .cloned()
+        .collect();
+    let mut removed: Vec<String> = prev_keys
+        .difference(&cur_keys)
+        .filter(|k| !moved.contains(*k))
+        .cloned()
+        .collect();
+
+    // A removed and an added path package in the same directory is a rename.
+    let removed_dirs: BTreeMap<&str, &DigestRow> = prev
+        .packages
+        .iter()
+        .filter(|r| removed.contains(&key(r)))
+        .filter_map(|r| Some((r.dir.as_deref()?, r)))
+        .collect();
+    let candidates: Vec<&DigestRow> = cur
+        .packages
+        .iter()
+        .filter(|r| added.contains(&key(r)))
+        .collect();
+    let mut renames = Vec::new();
+    for r in candidates {
+        if let Some(old) = r.dir.as_deref().and_then(|d| removed_dirs.get(d))
+            && old.name != r.name
+        {
+            renames.push((old.name.clone(), r.name.clone()));
+            added.retain(|k| *k != key(r));
+            removed.retain(|k| *k != key(old));
+        }
+    }
+
+    let mut previous_rank = BTreeMap::new();
+    for (i, r) in prev.packages.iter().enumerate() {
+        previous_rank.entry(r.name.clone()).or_insert(i + 1);
+    }
+    for (old, new) in &renames {
+        if let Some(&rank) = previous_rank.get(old) {
+            previous_rank.insert(new.clone(), rank);
+        }
+    }
+
+    DigestDelta {
+        since: prev.generated_at,
+        nodes: cur.nodes as i64 - prev.nodes as i64,
+        edges: cur.edges as i64 - prev.edges as i64,
+        added,
+        removed,
+        version_changes,
+        renames,
+        previous_rank,
+    }
+}
+
+fn rank_change(delta: Option<&DigestDelta>, name: &str, rank: usize) -> String {
+    let Some(delta) = delta else {
+        return String::new();
+    };
+    match delta.previous_rank.get(name) {
+        None => "new".to_string(),
+        Some(&prev) if prev > rank => format!("↑{}", prev - rank),
+        Some(&prev) if prev < rank => format!("↓{}", rank - prev),
+        Some(_) => "=".to_string(),
+    }
+}
+
+pub fn render_text(state: &DigestState, delta: Option<&DigestDelta>, top: usize) -> String {
+    let mut s = String::new();
+    writeln!(
+        s,
+        "pkgrank digest {}",
+        time::format_date(state.generated_at)
+    )
+    .unwrap();
+    write!(s, "{} packages, {} edges", state.nodes, state.edges).unwrap();
+    if let Some(d) = delta {
+        write!(
+            s,
+            " ({:+} packages, {:+} edges since {})",
+            d.nodes,
+            d.edges,
+            time::format_date(d.since)
+        )
+        .unwrap();
+    }
+    writeln!(s).unwrap();
+
+    writeln!(s, "\nTop {} by Pagerank:", top).unwrap();
+    writeln!(s, "{:─<50}", "").unwrap();
+    for (i, r) in state.packages.iter().take(top).enumerate() {
+        let line = format!(
+            "{:3}. {:40} {:.6} {}",
+            i + 1,
+            r.name,
+            r.score,
+            rank_change(delta, &r.name, i + 1)
+        );
+        writeln!(s, "{}", line.trim_end()).unwrap();
+    }
+
+    if let Some(d) = delta {
+        for (title, items) in [("Added", &d.added), ("Removed", &d.removed)] {
+            if !items.is_empty() {
+                writeln!(s, "\n{}: {}", title, items.join(", ")).unwrap();
+            }
+        }
+        if !d.version_changes.is_empty() {
+            writeln!(s, "\nVersion changes:").unwrap();
+            for (name, old, new) in &d.version_changes {
+                writeln!(s, "  {} {} -> {}", name, old, new).unwrap();
+            }
+        }
+        if !d.renames.is_empty() {
+            writeln!(s, "\nRenamed:").unwrap();
+            for (old, new) in &d.renames {
+                writeln!(s, "  {} -> {}", old, new).unwrap();
+            }
+        }
+    }
+    s
+}
+
+pub fn render_markdown(state: &DigestState, delta: Option<&DigestDelta>, top: usize) -> String {
+    let mut s = String::new();
+    writeln!(
+        s,
+        "## pkgrank digest {}\n",
+        time::format_date(state.generated_at)
+    )
+    .unwrap();
+    write!(s, "{} packages, {} edges", state.nodes, state.edges).unwrap();
+    if let Some(d) = delta {
+        write!(
+            s,
+            " ({:+} packages, {:+} edges since {})",
+            d.nodes,
+            d.edges,
+            time::format_date(d.since)
+        )
+        .unwrap();
+    }
+    writeln!(s).unwrap();
+
+    writeln!(s, "\n### Top {} by PageRank\n", top).unwrap();
+    writeln!(s, "| # | Package | Score | Change |").unwrap();
+    writeln!(s, "|---|---|---|---|").unwrap();
+    for (i, r) in state.packages.iter().take(top).enumerate() {
+        writeln!(
+            s,
+            "| {} | `{}` {} | {:.6} | {} |",
+            i + 1,
+            r.name,
+            r.version,
+            r.score,
+            rank_change(delta, &r.name, i + 1)
+        )
+        .unwrap();
+    }
+
+    if let Some(d) = delta {
+        for (title, items) in [("Added", &d.added), ("Removed", &d.removed)] {
+            if !items.is_empty() {
+                writeln!(s, "\n### {}\n", title).unwrap();
+                for item in items {
+                    writeln!(s, "- `{}`", item).unwrap();
+                }
+            }
+        }
+        if !d.version_changes.is_empty() {
+            writeln!(s, "\n### Version changes\n").unwrap();
+            for (name, old, new) in &d.version_changes {
+                writeln!(s, "- `{}` {} → {}", name, old, new).unwrap();
+            }
+        }
+        if !d.renames.is_empty() {
+            writeln!(s, "\n### Renamed\n").unwrap();
+            for (old, new) in &d.renames {
+                writeln!(s, "- `{}` → `{}`", old, new).unwrap();
+            }
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(name: &str, version: &str, dir: Option<&str>) -> DigestRow {
+        DigestRow {
+            name: name.to_string(),
+            version: version.to_string(),
+            score: 0.0,
+            workspace_member: dir.is_some(),
+            dir: dir.map(str::to_string),
+        }
+    }
+
+    fn state(packages: Vec<DigestRow>) -> DigestState {
+        DigestState {
+            generated_at: 0,
+            nodes: packages.len(),
+            edges: 0,
+            packages,
+        }
+    }
+
+    #[test]
+    fn path_package_in_the_same_directory_is_a_rename() {
+        let prev = state(vec![
+            row("core", "0.1.0", Some("/ws/core")),
+            row("serde", "1.0.0", None),
+        ]);
+        let cur = state(vec![
+            row("serde", "1.0.0", None),
+            row("platform", "0.1.0", Some("/ws/core")),
+        ]);
+        let delta = diff(&prev, &cur);
+        assert_eq!(
+            delta.renames,
+            [("core".to_string(), "platform".to_string())]
+        );
+        assert!(delta.added.is_empty());
+        assert!(delta.removed.is_empty());
+        assert_eq!(delta.previous_rank.get("platform"), Some(&1));
+    }
+
+    #[test]
+    fn registry_packages_and_other_directories_are_not_renames() {
+        let prev = state(vec![
+            row("a", "0.1.0", Some("/ws/a")),
+            row("left-pad", "1.0.0", None),
+        ]);
+        let cur = state(vec![
+            row("b", "0.1.0", Some("/ws/b")),
+            row("right-pad", "1.0.0", None),
+        ]);
+        let delta = diff(&prev, &cur);
+        assert!(delta.renames.is_empty());
+        assert_eq!(delta.added, ["b 0.1.0", "right-pad 1.0.0"]);
+        assert_eq!(delta.removed, ["a 0.1.0", "left-pad 1.0.0"]);
+    }
+
+    #[test]
+    fn version_bump_is_not_add_and_remove() {
+        let prev = state(vec![row("serde", "1.0.0", None)]);
+        let cur = state(vec![row("serde", "1.0.1", None)]);
+        let delta = diff(&prev, &cur);
+        assert_eq!(
+            delta.version_changes,
+            [(
+                "serde".to_string(),
+                "1.0.0".to_string(),
+                "1.0.1".to_string()
+            )]
+        );
+        assert!(delta.added.is_empty() && delta.removed.is_empty() && delta.renames.is_empty());
+    }
+}
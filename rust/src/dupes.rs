@@ -0,0 +1,160 @@
+//! `pkgrank dupes`: third-party packages resolved at more than one version.
+
+use crate::graph::DepGraph;
+use cargo_metadata::semver::Version;
+use petgraph::prelude::*;
+use petgraph::visit::{Bfs, Reversed, Walker};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DupeVersion {
+    pub version: String,
+    /// Packages that depend on this version directly.
+    pub dependents: Vec<String>,
+    /// Workspace members that pull this version in, directly or transitively.
+    pub workspace_members: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Dupe {
+    pub name: String,
+    pub versions: Vec<DupeVersion>,
+}
+
+pub fn dupes(graph: &DepGraph) -> Vec<Dupe> {
+    let mut by_name: BTreeMap<&str, Vec<NodeIndex>> = BTreeMap::new();
+    for i in graph.node_indices().filter(|&i| !graph[i].workspace_member) {
+        by_name.entry(&graph[i].name).or_default().push(i);
+    }
+
+    by_name
+        .into_iter()
+        .filter(|(_, nodes)| nodes.len() > 1)
+        .map(|(name, mut nodes)| {
+            nodes.sort_by_key(|&i| Version::parse(&graph[i].version).ok());
+            let versions = nodes
+                .into_iter()
+                .map(|i| {
+                    let mut dependents: Vec<String> = graph
+                        .neighbors_directed(i, Direction::Incoming)
+                        .map(|j| format!("{} {}", graph[j].name, graph[j].version))
+                        .collect();
+                    dependents.sort();
+                    dependents.dedup();
+
+                    let mut workspace_members: Vec<String> = Bfs::new(Reversed(graph), i)
+                        .iter(Reversed(graph))
+                        .filter(|&j| graph[j].workspace_member)
+                        .map(|j| graph[j].name.clone())
+                        .collect();
+                    workspace_members.sort();
+
+                    DupeVersion {
+                        version: graph[i].version.clone(),
+                        dependents,
+                        workspace_members,
+                    }
+                })
+                .collect();
+            Dupe {
+                name: name.to_string(),
+                versions,
+            }
+        })
+        .collect()
+}
+
+pub fn print_text(dupes: &[Dupe]) {
+    println!("Packages with multiple resolved versions: {}", dupes.len());
+    println!("{:─<50}", "");
+    for dupe in dupes {
+        println!("{}", dupe.name);
+        for v in &dupe.versions {
+            let via = if v.dependents.is_empty() {
+                "(no edges under the selected dependency kinds)".to_string()
+            } else {
+                format!("via {}", v.dependents.join(", "))
+            };
+            println!("  {:12} {}", v.version, via);
+            if !v.workspace_members.is_empty() {
+                println!(
+                    "  {:12} pulled in by {}",
+                    "",
+                    v.workspace_members.join(", ")
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Dep, Package};
+
+    fn package(name: &str, version: &str, member: bool) -> Package {
+        Package {
+            id: format!("{}@{}", name, version),
+            name: name.to_string(),
+            version: version.to_string(),
+            manifest_path: Default::default(),
+            license: None,
+            source: None,
+            owner: None,
+            workspace_member: member,
+            workspace: None,
+            targets: vec![],
+        }
+    }
+
+    #[test]
+    fn versions_sort_by_semver_and_trace_back_to_members() {
+        let mut graph = DepGraph::new();
+        let app = graph.add_node(package("app", "0.1.0", true));
+        let tool = graph.add_node(package("tool", "0.1.0", true));
+        let mid = graph.add_node(package("mid", "1.0.0", false));
+        let new = graph.add_node(package("syn", "1.10.0", false));
+        let old = graph.add_node(package("syn", "1.9.0", false));
+        let _single = graph.add_node(package("quote", "1.0.0", false));
+        let dep = || Dep {
+            kinds: vec![cargo_metadata::DependencyKind::Normal],
+            weight: 1.0,
+            targets: vec![],
+        };
+        graph.add_edge(app, mid, dep());
+        graph.add_edge(mid, new, dep());
+        graph.add_edge(tool, old, dep());
+
+        let dupes = dupes(&graph);
+        assert_eq!(dupes.len(), 1);
+        assert_eq!(dupes[0].name, "syn");
+        let versions: Vec<(&str, &[String], &[String])> = dupes[0]
+            .versions
+            .iter()
+            .map(|v| {
+                (
+                    v.version.as_str(),
+                    v.dependents.as_slice(),
+                    v.workspace_members.as_slice(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            versions,
+            [
+                (
+                    "1.9.0",
+                    &["tool 0.1.0".to_string()][..],
+                    &["tool".to_string()][..]
+                ),
+                (
+                    "1.10.0",
+                    &["mid 1.0.0".to_string()][..],
+                    &["app".to_string()][..]
+                ),
+            ]
+        );
+    }
+}
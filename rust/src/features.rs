@@ -0,0 +1,228 @@
+//! `pkgrank features-impact`: what enabling features pulls into the graph.
+
+use crate::centrality::{self, PageRankConfig};
+use crate::graph::DepGraph;
+use petgraph::prelude::*;
+use petgraph::visit::{Bfs, Walker};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GraphSize {
+    pub nodes: usize,
+    pub edges: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PackageChange {
+    pub name: String,
+    pub version: String,
+    pub pagerank: f64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct EdgeChange {
+    pub from: String,
+    pub to: String,
+    pub kinds: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ScoreDelta {
+    pub name: String,
+    pub version: String,
+    pub baseline: f64,
+    pub with_features: f64,
+    pub delta: f64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FeaturesImpactOut {
+    pub features: Vec<String>,
+    pub baseline: GraphSize,
+    pub with_features: GraphSize,
+    pub added_packages: Vec<PackageChange>,
+    pub removed_packages: Vec<PackageChange>,
+    pub added_edges: Vec<EdgeChange>,
+    pub removed_edges: Vec<EdgeChange>,
+    /// Largest pagerank moves among packages present in both graphs.
+    pub score_deltas: Vec<ScoreDelta>,
+}
+
+/// The part of a graph that is actually built: workspace members and
+/// everything reachable from them.
+struct Active<'a> {
+    graph: &'a DepGraph,
+    pagerank: Vec<f64>,
+    nodes: BTreeMap<String, NodeIndex>,
+    edges: BTreeMap<(String, String), EdgeIndex>,
+}
+
+impl<'a> Active<'a> {
+    fn new(graph: &'a DepGraph, config: &PageRankConfig) -> Self {
+        let mut reachable = vec![false; graph.node_count()];
+        for member in graph.node_indices().filter(|&i| graph[i].workspace_member) {
+            for i in Bfs::new(graph, member).iter(graph) {
+                reachable[i.index()] = true;
+            }
+        }
+
+        let (pagerank, _) = centrality::pagerank(graph, config);
+        let nodes: BTreeMap<String, NodeIndex> = graph
+            .node_indices()
+            .filter(|i| reachable[i.index()])
+            .map(|i| (key(graph, i), i))
+            .collect();
+        let edges = graph
+            .edge_references()
+            .filter(|e| reachable[e.source().index()])
+            .map(|e| ((key(graph, e.source()), key(graph, e.target())), e.id()))
+            .collect();
+
+        Self {
+            graph,
+            pagerank,
+            nodes,
+            edges,
+        }
+    }
+
+    fn size(&self) -> GraphSize {
+        GraphSize {
+            nodes: self.nodes.len(),
+            edges: self.edges.len(),
+        }
+    }
+
+    fn packages_not_in(&self, other: &Active) -> Vec<PackageChange> {
+        self.nodes
+            .iter()
+            .filter(|(k, _)| !other.nodes.contains_key(*k))
+            .map(|(_, &i)| PackageChange {
+                name: self.graph[i].name.clone(),
+                version: self.graph[i].version.clone(),
+                pagerank: self.pagerank[i.index()],
+            })
+            .collect()
+    }
+
+    fn edges_not_in(&self, other: &Active) -> Vec<EdgeChange> {
+        self.edges
+            .iter()
+            .filter(|(k, _)| !other.edges.contains_key(*k))
+            .map(|((from, to), &e)| EdgeChange {
+                from: from.clone(),
+                to: to.clone(),
+                kinds: self.graph[e].kinds.iter().map(|k| k.to_string()).collect(),
+            })
+            .collect()
+    }
+}
+
+fn key(graph: &DepGraph, i: NodeIndex) -> String {
+    format!("{}@{}", graph[i].name, graph[i].version)
+}
+
+pub fn features_impact(
+    baseline: &DepGraph,
+    with_features: &DepGraph,
+    features: Vec<String>,
+    top: usize,
+    config: &PageRankConfig,
+) -> FeaturesImpactOut {
+    let base = Active::new(baseline, config);
+    let feat = Active::new(with_features, config);
+
+    let base_scores: HashMap<&str, f64> = base
+        .nodes
+        .iter()
+        .map(|(k, &i)| (k.as_str(), base.pagerank[i.index()]))
+        .collect();
+    let mut score_deltas: Vec<ScoreDelta> = feat
+        .nodes
+        .iter()
+        .filter_map(|(k, &i)| {
+            let before = *base_scores.get(k.as_str())?;
+            let after = feat.pagerank[i.index()];
+            Some(ScoreDelta {
+                name: with_features[i].name.clone(),
+                version: with_features[i].version.clone(),
+                baseline: before,
+                with_features: after,
+                delta: after - before,
+            })
+        })
+        .collect();
+    score_deltas.retain(|d| d.delta.abs() > 1e-12);
+    score_deltas.sort_by(|a, b| {
+        centrality::by_score_desc(a.delta.abs(), b.delta.abs())
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.version.cmp(&b.version))
+    });
+    score_deltas.truncate(top);
+
+    FeaturesImpactOut {
+        features,
+        baseline: base.size(),
+        with_features: feat.size(),
+        added_packages: feat.packages_not_in(&base),
+        removed_packages: base.packages_not_in(&feat),
+        added_edges: feat.edges_not_in(&base),
+        removed_edges: base.edges_not_in(&feat),
+        score_deltas,
+    }
+}
+
+pub fn print_text(out: &FeaturesImpactOut) {
+    println!("Feature impact of {}:", out.features.join(", "));
+    println!(
+        "  baseline: {} packages, {} edges; with features: {} packages, {} edges",
+        out.baseline.nodes, out.baseline.edges, out.with_features.nodes, out.with_features.edges
+    );
+
+    for (title, packages) in [
+        ("Added packages", &out.added_packages),
+        ("Removed packages", &out.removed_packages),
+    ] {
+        if packages.is_empty() {
+            continue;
+        }
+        println!("\n{}:", title);
+        println!("{:─<50}", "");
+        for (i, p) in packages.iter().enumerate() {
+            let label = format!("{} {}", p.name, p.version);
+            println!("{:3}. {:40} {:.6}", i + 1, label, p.pagerank);
+        }
+    }
+
+    for (title, edges) in [
+        ("Added edges", &out.added_edges),
+        ("Removed edges", &out.removed_edges),
+    ] {
+        if edges.is_empty() {
+            continue;
+        }
+        println!("\n{}:", title);
+        println!("{:─<50}", "");
+        for e in edges {
+            println!("  {} -> {} ({})", e.from, e.to, e.kinds.join(", "));
+        }
+    }
+
+    if !out.score_deltas.is_empty() {
+        println!("\nLargest Pagerank changes:");
+        println!("{:─<50}", "");
+        for (i, d) in out.score_deltas.iter().enumerate() {
+            let label = format!("{} {}", d.name, d.version);
+            println!(
+                "{:3}. {:40} {:.6} -> {:.6} ({:+.6})",
+                i + 1,
+                label,
+                d.baseline,
+                d.with_features,
+                d.delta
+            );
+        }
+    }
+}
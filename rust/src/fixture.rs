@@ -0,0 +1,149 @@
+//! `pkgrank gen-fixture`: synthetic workspaces with known structure.
+
+use anyhow::{Context, bail};
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, ValueEnum, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// One workspace with every crate under `crates/`
+    Workspace,
+    /// The upper half of the crates in a workspace of its own under
+    /// `nested/`, excluded from the root (see `--nested`)
+    Nested,
+}
+
+/// What was generated, written to `fixture.json` next to the workspace.
+/// Not an artifact: a description of generated input, so it has no
+/// provenance envelope.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Fixture {
+    pub layout: Layout,
+    pub seed: u64,
+    /// Crate names; a crate only depends on crates earlier in the list.
+    pub crates: Vec<String>,
+    /// `[dependent, dependency]` pairs.
+    pub edges: Vec<[String; 2]>,
+}
+
+/// SplitMix64, so fixtures are reproducible without an RNG dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Pick up to `edges` distinct dependent -> dependency pairs, always from a
+/// later crate to an earlier one so the graph is acyclic.
+pub fn plan(crates: usize, edges: usize, layout: Layout, seed: u64) -> Fixture {
+    let names: Vec<String> = (0..crates).map(|i| format!("crate_{:03}", i)).collect();
+    let max_edges = crates * crates.saturating_sub(1) / 2;
+    let mut rng = Rng(seed);
+    let mut chosen = BTreeSet::new();
+    while chosen.len() < edges.min(max_edges) {
+        let a = rng.below(crates);
+        let b = rng.below(crates);
+        if a != b {
+            chosen.insert((a.max(b), a.min(b)));
+        }
+    }
+    Fixture {
+        layout,
+        seed,
+        edges: chosen
+            .into_iter()
+            .map(|(from, to)| [names[from].clone(), names[to].clone()])
+            .collect(),
+        crates: names,
+    }
+}
+
+/// Write `fixture` as a Cargo workspace under `dir`, which must be empty or
+/// missing.
+pub fn write(dir: &Path, fixture: &Fixture) -> anyhow::Result<()> {
+    if dir
+        .read_dir()
+        .is_ok_and(|mut entries| entries.next().is_some())
+    {
+        bail!("{} is not empty", dir.display());
+    }
+    let nested_from = match fixture.layout {
+        Layout::Workspace => fixture.crates.len(),
+        Layout::Nested => fixture.crates.len() / 2,
+    };
+    // Every crate sits at `<group>/<name>`, so paths between crates are
+    // always `../../<group>/<name>`.
+    let group = |i: usize| if i < nested_from { "crates" } else { "nested" };
+    let crate_dir = |i: usize| dir.join(group(i)).join(&fixture.crates[i]);
+
+    let mut root = String::from("[workspace]\nmembers = [\"crates/*\"]\n");
+    if nested_from < fixture.crates.len() {
+        root.push_str("exclude = [\"nested\"]\n");
+        write_file(
+            &dir.join("nested/Cargo.toml"),
+            "[workspace]\nmembers = [\"crate_*\"]\nresolver = \"2\"\n",
+        )?;
+    }
+    root.push_str("resolver = \"2\"\n");
+    write_file(&dir.join("Cargo.toml"), &root)?;
+
+    for (i, name) in fixture.crates.iter().enumerate() {
+        let deps: Vec<usize> = fixture
+            .edges
+            .iter()
+            .filter(|[from, _]| from == name)
+            .filter_map(|[_, to]| fixture.crates.iter().position(|c| c == to))
+            .collect();
+
+        let mut manifest = format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\npublish = false\n\n[dependencies]\n",
+            name
+        );
+        let mut lib = format!(
+            "//! Generated by pkgrank gen-fixture.\n\npub fn id() -> &'static str {{\n    \"{}\"\n}}\n\npub fn deps() -> Vec<&'static str> {{\n    vec![",
+            name
+        );
+        for (k, &d) in deps.iter().enumerate() {
+            let name = &fixture.crates[d];
+            writeln!(
+                manifest,
+                "{} = {{ path = \"../../{}/{}\" }}",
+                name,
+                group(d),
+                name
+            )?;
+            if k > 0 {
+                lib.push_str(", ");
+            }
+            write!(lib, "{}::id()", fixture.crates[d])?;
+        }
+        lib.push_str("]\n}\n");
+
+        write_file(&crate_dir(i).join("Cargo.toml"), &manifest)?;
+        write_file(&crate_dir(i).join("src/lib.rs"), &lib)?;
+    }
+
+    crate::artifacts::write_json(&dir.join("fixture.json"), fixture)
+}
+
+fn write_file(path: &Path, contents: &str) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, contents).with_context(|| format!("writing {}", path.display()))
+}
@@ -0,0 +1,126 @@
+//! `pkgrank gateways`: workspace crates that isolate a third-party subtree.
+
+use crate::centrality::{self, PageRankConfig};
+use crate::graph::DepGraph;
+use petgraph::prelude::*;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GatewayRow {
+    pub name: String,
+    pub version: String,
+    /// Third-party packages the rest of the workspace only reaches through
+    /// this crate.
+    pub gated: usize,
+    /// Sum of the gated packages' pagerank.
+    pub gated_pagerank: f64,
+    /// Gated packages this crate depends on directly, sorted.
+    pub entry_points: Vec<String>,
+    /// Workspace crates that depend on this one directly.
+    pub dependents: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+/// Workspace members that are the only way from the rest of the workspace
+/// into at least `min_gated` third-party packages, largest subtree first.
+pub fn gateways(graph: &DepGraph, min_gated: usize, config: &PageRankConfig) -> Vec<GatewayRow> {
+    let members: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|&i| graph[i].workspace_member)
+        .collect();
+    if members.len() < 2 {
+        return vec![];
+    }
+    let (pagerank, _) = centrality::pagerank(graph, config);
+
+    let mut rows: Vec<GatewayRow> = members
+        .iter()
+        .filter_map(|&gateway| {
+            let through = reachable(graph, &[gateway], None);
+            let others: Vec<NodeIndex> =
+                members.iter().copied().filter(|&m| m != gateway).collect();
+            let around = reachable(graph, &others, Some(gateway));
+            let gated: Vec<NodeIndex> = graph
+                .node_indices()
+                .filter(|&i| through[i.index()] && !around[i.index()] && !graph[i].workspace_member)
+                .collect();
+            if gated.is_empty() || gated.len() < min_gated {
+                return None;
+            }
+
+            let mut entry_points: Vec<String> = graph
+                .neighbors_directed(gateway, Direction::Outgoing)
+                .filter(|j| gated.contains(j))
+                .map(|j| format!("{}@{}", graph[j].name, graph[j].version))
+                .collect();
+            entry_points.sort();
+            entry_points.dedup();
+
+            let pkg = &graph[gateway];
+            Some(GatewayRow {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                gated: gated.len(),
+                gated_pagerank: gated.iter().map(|i| pagerank[i.index()]).sum(),
+                entry_points,
+                dependents: graph
+                    .neighbors_directed(gateway, Direction::Incoming)
+                    .filter(|&j| j != gateway && graph[j].workspace_member)
+                    .count(),
+                owner: pkg.owner.clone(),
+            })
+        })
+        .collect();
+
+    rows.sort_by(|a, b| {
+        b.gated
+            .cmp(&a.gated)
+            .then_with(|| centrality::by_score_desc(a.gated_pagerank, b.gated_pagerank))
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.version.cmp(&b.version))
+    });
+    rows
+}
+
+/// Nodes reachable from `starts` (inclusive) without passing through `avoid`.
+fn reachable(graph: &DepGraph, starts: &[NodeIndex], avoid: Option<NodeIndex>) -> Vec<bool> {
+    let mut seen = vec![false; graph.node_count()];
+    let mut stack: Vec<NodeIndex> = starts.to_vec();
+    for s in starts {
+        seen[s.index()] = true;
+    }
+    while let Some(v) = stack.pop() {
+        for w in graph.neighbors_directed(v, Direction::Outgoing) {
+            if Some(w) != avoid && !std::mem::replace(&mut seen[w.index()], true) {
+                stack.push(w);
+            }
+        }
+    }
+    seen
+}
+
+pub fn print_text(rows: &[GatewayRow], top: usize) {
+    println!("Gateway crates (sole workspace path into third-party packages):");
+    println!("{:─<50}", "");
+    if rows.is_empty() {
+        println!("  none");
+        return;
+    }
+    for (i, row) in rows.iter().take(top).enumerate() {
+        let label = format!("{} {}", row.name, row.version);
+        let line = format!(
+            "{:3}. {:40} {:5} gated {:.6}  {}",
+            i + 1,
+            label,
+            row.gated,
+            row.gated_pagerank,
+            row.owner.as_deref().unwrap_or("")
+        );
+        println!("{}", line.trim_end());
+        if !row.entry_points.is_empty() {
+            println!("     via {}", row.entry_points.join(", "));
+        }
+    }
+}
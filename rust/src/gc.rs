@@ -0,0 +1,255 @@
+//! `pkgrank gc`: prune old snapshots under `<out>/runs/`.
+
+use crate::artifacts;
+use crate::snapshot::{self, SnapshotManifest};
+use crate::time;
+use anyhow::Context;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Retention {
+    /// Remove snapshots created more than this many days ago.
+    pub max_age_days: Option<u64>,
+    /// Then remove the oldest snapshots until the rest fit in this many bytes.
+    pub max_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GcOut {
+    pub dry_run: bool,
+    pub kept: usize,
+    pub kept_bytes: u64,
+    /// Oldest first.
+    pub removed: Vec<PrunedRun>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PrunedRun {
+    pub label: String,
+    pub created_at: u64,
+    pub bytes: u64,
+    pub reason: PruneReason,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PruneReason {
+    Age,
+    Size,
+}
+
+struct Run {
+    path: PathBuf,
+    label: String,
+    created_at: u64,
+    bytes: u64,
+}
+
+/// Apply `retention` to the snapshots of artifact directory `dir`.
+/// Directories under `runs/` without a readable manifest are left alone.
+pub fn gc(dir: &Path, retention: Retention, dry_run: bool) -> anyhow::Result<GcOut> {
+    let runs = runs(&dir.join(snapshot::RUNS_DIR))?;
+    let (runs, prune) = select(runs, retention, time::now_secs());
+
+    if !dry_run {
+        for (run, _) in &prune {
+            std::fs::remove_dir_all(&run.path)
+                .with_context(|| format!("removing {}", run.path.display()))?;
+        }
+    }
+    Ok(GcOut {
+        dry_run,
+        kept: runs.len(),
+        kept_bytes: runs.iter().map(|r| r.bytes).sum(),
+        removed: prune
+            .into_iter()
+            .map(|(run, reason)| PrunedRun {
+                label: run.label,
+                created_at: run.created_at,
+                bytes: run.bytes,
+                reason,
+            })
+            .collect(),
+    })
+}
+
+/// Split `runs` into those kept and those pruned as of `now`, both oldest
+/// first. A run exactly `max_age_days` old, or one that brings the total to
+/// exactly `max_bytes`, is kept.
+fn select(
+    mut runs: Vec<Run>,
+    retention: Retention,
+    now: u64,
+) -> (Vec<Run>, Vec<(Run, PruneReason)>) {
+    runs.sort_by(|a, b| {
+        a.created_at
+            .cmp(&b.created_at)
+            .then_with(|| a.label.cmp(&b.label))
+    });
+
+    let mut prune: Vec<(Run, PruneReason)> = Vec::new();
+    if let Some(days) = retention.max_age_days {
+        let cutoff = now.saturating_sub(days.saturating_mul(86_400));
+        let young = runs.split_off(runs.partition_point(|r| r.created_at < cutoff));
+        prune.extend(runs.drain(..).map(|r| (r, PruneReason::Age)));
+        runs = young;
+    }
+    if let Some(budget) = retention.max_bytes {
+        let mut total: u64 = runs.iter().map(|r| r.bytes).sum();
+        let mut over = 0;
+        while total > budget && over < runs.len() {
+            total -= runs[over].bytes;
+            over += 1;
+        }
+        prune.extend(runs.drain(..over).map(|r| (r, PruneReason::Size)));
+    }
+    (runs, prune)
+}
+
+fn runs(runs_dir: &Path) -> anyhow::Result<Vec<Run>> {
+    let entries = match std::fs::read_dir(runs_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", runs_dir.display())),
+    };
+    let mut runs = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let Ok(manifest) = artifacts::read::<SnapshotManifest>(&artifacts::SNAPSHOT.path(&path))
+        else {
+            continue;
+        };
+        runs.push(Run {
+            bytes: dir_size(&path)?,
+            path,
+            label: manifest.label,
+            created_at: manifest.created_at,
+        });
+    }
+    Ok(runs)
+}
+
+fn dir_size(dir: &Path) -> anyhow::Result<u64> {
+    let mut total = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        total += if meta.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            meta.len()
+        };
+    }
+    Ok(total)
+}
+
+pub fn print_text(out: &GcOut) {
+    let verb = if out.dry_run {
+        "Would remove"
+    } else {
+        "Removed"
+    };
+    println!("{} {} snapshots:", verb, out.removed.len());
+    println!("{:─<50}", "");
+    for run in &out.removed {
+        let reason = match run.reason {
+            PruneReason::Age => "age",
+            PruneReason::Size => "size",
+        };
+        println!(
+            "{:30} {}  {:10} bytes  {}",
+            run.label,
+            time::format_date(run.created_at),
+            run.bytes,
+            reason
+        );
+    }
+    println!("kept {} snapshots, {} bytes", out.kept, out.kept_bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86_400;
+    const NOW: u64 = 100 * DAY;
+
+    fn run(label: &str, age_days: u64, bytes: u64) -> Run {
+        Run {
+            path: PathBuf::new(),
+            label: label.to_string(),
+            created_at: NOW - age_days * DAY,
+            bytes,
+        }
+    }
+
+    fn labels(runs: &[Run]) -> Vec<&str> {
+        runs.iter().map(|r| r.label.as_str()).collect()
+    }
+
+    fn pruned(prune: &[(Run, PruneReason)]) -> Vec<&str> {
+        prune.iter().map(|(r, _)| r.label.as_str()).collect()
+    }
+
+    #[test]
+    fn age_cutoff_keeps_a_run_exactly_max_age_old() {
+        let runs = vec![run("new", 0, 1), run("edge", 7, 1), run("old", 8, 1)];
+        let retention = Retention {
+            max_age_days: Some(7),
+            max_bytes: None,
+        };
+        let (kept, prune) = select(runs, retention, NOW);
+        assert_eq!(labels(&kept), ["edge", "new"]);
+        assert_eq!(pruned(&prune), ["old"]);
+        assert!(matches!(prune[0].1, PruneReason::Age));
+    }
+
+    #[test]
+    fn huge_max_age_keeps_everything() {
+        let retention = Retention {
+            max_age_days: Some(u64::MAX),
+            max_bytes: None,
+        };
+        let (kept, prune) = select(vec![run("a", 99, 1)], retention, NOW);
+        assert_eq!(kept.len(), 1);
+        assert!(prune.is_empty());
+    }
+
+    #[test]
+    fn size_budget_removes_oldest_until_the_rest_fit() {
+        let runs = || vec![run("c", 1, 10), run("a", 3, 10), run("b", 2, 10)];
+        let budget = |max_bytes| Retention {
+            max_age_days: None,
+            max_bytes: Some(max_bytes),
+        };
+
+        let (kept, prune) = select(runs(), budget(30), NOW);
+        assert_eq!(labels(&kept), ["a", "b", "c"]);
+        assert!(prune.is_empty());
+
+        let (kept, prune) = select(runs(), budget(29), NOW);
+        assert_eq!(labels(&kept), ["b", "c"]);
+        assert_eq!(pruned(&prune), ["a"]);
+        assert!(matches!(prune[0].1, PruneReason::Size));
+
+        let (kept, prune) = select(runs(), budget(0), NOW);
+        assert!(kept.is_empty());
+        assert_eq!(pruned(&prune), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn age_applies_before_size() {
+        let runs = vec![run("old", 10, 100), run("mid", 2, 10), run("new", 0, 10)];
+        let retention = Retention {
+            max_age_days: Some(5),
+            max_bytes: Some(15),
+        };
+        let (kept, prune) = select(runs, retention, NOW);
+        assert_eq!(labels(&kept), ["new"]);
+        assert_eq!(pruned(&prune), ["old", "mid"]);
+        assert!(matches!(prune[0].1, PruneReason::Age));
+        assert!(matches!(prune[1].1, PruneReason::Size));
+    }
+}
@@ -0,0 +1,156 @@
+//! Thin wrappers around the `git` CLI.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Days since the last commit touching `dir`, or `None` if `dir` has no
+/// history (untracked, or not inside a git repository).
+pub fn last_commit_age_days(dir: &Path) -> Option<u64> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%ct", "--", "."])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let timestamp: u64 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(now.saturating_sub(timestamp) / 86_400)
+}
+
+/// Recent change volume under a directory.
+#[derive(Debug, Clone, Copy, Default, Serialize, JsonSchema)]
+pub struct Churn {
+    pub commits: usize,
+    /// Lines added plus lines deleted (binary files not counted).
+    pub lines: u64,
+}
+
+/// Commits and changed lines touching `dir` in the last `days` days, or
+/// `None` if `dir` is not inside a git repository.
+pub fn churn(dir: &Path, days: u64) -> Option<Churn> {
+    let output = Command::new("git")
+        .args([
+            "log",
+            &format!("--since={} days ago", days),
+            "--numstat",
+            "--format=format:commit",
+            "--",
+            ".",
+        ])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut churn = Churn::default();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if line == "commit" {
+            churn.commits += 1;
+            continue;
+        }
+        let mut fields = line.split('\t');
+        for count in [fields.next(), fields.next()].into_iter().flatten() {
+            churn.lines += count.parse::<u64>().unwrap_or(0);
+        }
+    }
+    Some(churn)
+}
+
+/// Files changed by each commit touching `dir` in the last `days` days,
+/// as absolute paths, or `None` if `dir` is not inside a git repository.
+pub fn commit_files(dir: &Path, days: u64) -> Option<Vec<Vec<PathBuf>>> {
+    let root = toplevel(dir)?;
+    let output = Command::new("git")
+        .args([
+            "log",
+            &format!("--since={} days ago", days),
+            "--name-only",
+            "--format=format:commit",
+            "--",
+            ".",
+        ])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut commits: Vec<Vec<PathBuf>> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        match line {
+            "commit" => commits.push(vec![]),
+            "" => {}
+            file => {
+                if let Some(files) = commits.last_mut() {
+                    files.push(root.join(file));
+                }
+            }
+        }
+    }
+    Some(commits)
+}
+
+/// Commit count per author (`Name <email>`) for commits touching `dir`,
+/// optionally limited to the last `days` days, most active first.
+pub fn authors(dir: &Path, days: Option<u64>) -> Option<Vec<(String, usize)>> {
+    let mut args = vec!["shortlog".to_string(), "-sne".to_string()];
+    if let Some(days) = days {
+        args.push(format!("--since={} days ago", days));
+    }
+    args.extend(["HEAD", "--", "."].map(String::from));
+    let output = Command::new("git")
+        .args(&args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (count, author) = line.trim().split_once('\t')?;
+                Some((author.to_string(), count.trim().parse().ok()?))
+            })
+            .collect(),
+    )
+}
+
+/// Root of the git work tree containing `dir`.
+pub fn toplevel(dir: &Path) -> Option<PathBuf> {
+    single_line(dir, &["rev-parse", "--show-toplevel"]).map(PathBuf::from)
+}
+
+/// `git describe --always --dirty` for the work tree containing `dir`.
+pub fn describe(dir: &Path) -> Option<String> {
+    single_line(dir, &["describe", "--always", "--dirty"])
+}
+
+/// Full SHA of `HEAD` in the repository containing `dir`.
+pub fn head_sha(dir: &Path) -> Option<String> {
+    single_line(dir, &["rev-parse", "HEAD"])
+}
+
+/// Trimmed stdout of a git command that prints a single line.
+fn single_line(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!line.is_empty()).then_some(line)
+}
@@ -0,0 +1,123 @@
+//! Shell-style globs shared by CODEOWNERS rules and `--include`/`--exclude`.
+
+use std::path::Path;
+
+/// A package filter pattern.
+///
+/// Patterns containing `/` match the package's manifest directory (relative
+/// to the workspace root when inside it) segment by segment, with `**`
+/// spanning any number of segments; other patterns match the package name.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    pattern: String,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn matches(&self, name: &str, manifest_dir: &Path, workspace_root: &Path) -> bool {
+        if !self.pattern.contains('/') {
+            return wildcard(&self.pattern, name);
+        }
+        let dir = manifest_dir
+            .strip_prefix(workspace_root)
+            .unwrap_or(manifest_dir);
+        let path: Vec<&str> = dir
+            .iter()
+            .filter_map(|s| s.to_str())
+            .filter(|s| *s != "/")
+            .collect();
+        let pattern: Vec<&str> = self.pattern.split('/').filter(|s| !s.is_empty()).collect();
+        match_segments(&pattern, &path)
+    }
+}
+
+pub fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|i| match_segments(rest, &path[i..])),
+        Some((seg, rest)) => match path.split_first() {
+            Some((p, path_rest)) => wildcard(seg, p) && match_segments(rest, path_rest),
+            None => false,
+        },
+    }
+}
+
+/// `*` and `?` wildcard match within one path segment.
+pub fn wildcard(pattern: &str, text: &str) -> bool {
+    let (p, t): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    let (mut pi, mut ti) = (0, 0);
+    let (mut star, mut mark) = (None, 0);
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some(pi);
+            mark = ti;
+            pi += 1;
+        } else if let Some(s) = star {
+            pi = s + 1;
+            mark += 1;
+            ti = mark;
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard_stays_within_the_text() {
+        assert!(wildcard("serde*", "serde_json"));
+        assert!(wildcard("*-sys", "openssl-sys"));
+        assert!(wildcard("to?io", "tokio"));
+        assert!(wildcard("*", ""));
+        assert!(!wildcard("serde", "serde_json"));
+        assert!(!wildcard("?", ""));
+        assert!(!wildcard("*-sys", "openssl-sys2"));
+    }
+
+    #[test]
+    fn double_star_spans_any_number_of_segments() {
+        assert!(match_segments(&["**", "bench"], &["bench"]));
+        assert!(match_segments(
+            &["**", "bench"],
+            &["tools", "perf", "bench"]
+        ));
+        assert!(match_segments(&["crates", "**"], &["crates"]));
+        assert!(match_segments(
+            &["crates", "**", "*-sys"],
+            &["crates", "ffi", "zstd-sys"]
+        ));
+        assert!(!match_segments(&["**", "bench"], &["bench", "data"]));
+    }
+
+    #[test]
+    fn path_patterns_are_anchored_at_the_workspace_root() {
+        let root = Path::new("/ws");
+        let glob = Glob::new("crates/*");
+        assert!(glob.matches("a", Path::new("/ws/crates/a"), root));
+        assert!(!glob.matches("a", Path::new("/ws/vendor/crates/a"), root));
+        assert!(!glob.matches("a", Path::new("/ws/crates/a/sub"), root));
+        // A leading or trailing `/` changes nothing.
+        assert!(Glob::new("/crates/a/").matches("a", Path::new("/ws/crates/a"), root));
+        // Outside the root the absolute directory is matched.
+        assert!(Glob::new("/elsewhere/*").matches("x", Path::new("/elsewhere/x"), root));
+    }
+
+    #[test]
+    fn plain_patterns_match_the_name() {
+        let root = Path::new("/ws");
+        assert!(Glob::new("pkg*").matches("pkgrank", Path::new("/ws/other"), root));
+        assert!(!Glob::new("other").matches("pkgrank", Path::new("/ws/other"), root));
+    }
+}
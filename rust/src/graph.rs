@@ -0,0 +1,259 @@
+//! Dependency graph construction from `cargo metadata`.
+
+use crate::centrality::Weighted;
+use crate::glob::Glob;
+use anyhow::{Context, bail};
+use cargo_metadata::{CargoOpt, DependencyKind, Metadata, MetadataCommand, PackageId};
+use petgraph::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A resolved package.
+#[derive(Debug, Clone)]
+pub struct Package {
+    /// Cargo package ID (`PackageId::repr`), unique within one metadata
+    /// run even where name and version are not.
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub manifest_path: PathBuf,
+    /// SPDX license expression from the manifest.
+    pub license: Option<String>,
+    /// Source (registry or git URL); `None` for path dependencies.
+    pub source: Option<String>,
+    /// Owning team(s) from CODEOWNERS, space-separated.
+    pub owner: Option<String>,
+    pub workspace_member: bool,
+    /// Nested workspace (relative to the root) this member belongs to
+    /// (`--nested` only).
+    pub workspace: Option<String>,
+    /// Target triples this package resolves for (`--targets` only).
+    pub targets: Vec<String>,
+}
+
+/// A dependency edge (dependent -> dependency), with every kind it is declared under.
+#[derive(Debug, Clone)]
+pub struct Dep {
+    pub kinds: Vec<DependencyKind>,
+    /// Largest configured weight among `kinds`.
+    pub weight: f64,
+    /// Target triples this edge resolves for (`--targets` only).
+    pub targets: Vec<String>,
+}
+
+impl Weighted for Dep {
+    fn weight(&self) -> f64 {
+        self.weight
+    }
+}
+
+pub type DepGraph = DiGraph<Package, Dep>;
+
+/// Which dependency kinds become edges, and at what weight.
+///
+/// `None` (or a zero weight) leaves that kind out of the graph.
+#[derive(Debug, Clone, Copy)]
+pub struct GraphOptions {
+    pub normal: Option<f64>,
+    pub dev: Option<f64>,
+    pub build: Option<f64>,
+}
+
+impl Default for GraphOptions {
+    fn default() -> Self {
+        Self {
+            normal: Some(1.0),
+            dev: None,
+            build: None,
+        }
+    }
+}
+
+impl GraphOptions {
+    fn weight(&self, kind: DependencyKind) -> Option<f64> {
+        match kind {
+            DependencyKind::Normal => self.normal,
+            DependencyKind::Development => self.dev,
+            DependencyKind::Build => self.build,
+            _ => None,
+        }
+        .filter(|&w| w > 0.0)
+    }
+}
+
+/// Run `cargo metadata` for a `Cargo.toml` or a directory containing one.
+///
+/// With `filter_platform`, only dependencies that build for that target
+/// triple are resolved. `features` selects workspace features (default
+/// features when `None`).
+pub fn load_metadata(
+    path: &str,
+    filter_platform: Option<&str>,
+    features: Option<CargoOpt>,
+) -> anyhow::Result<Metadata> {
+    let manifest_path = if path.ends_with("Cargo.toml") {
+        path.to_string()
+    } else {
+        format!("{}/Cargo.toml", path)
+    };
+
+    let mut cmd = MetadataCommand::new();
+    cmd.manifest_path(&manifest_path);
+    if let Some(triple) = filter_platform {
+        cmd.other_options(vec!["--filter-platform".to_string(), triple.to_string()]);
+    }
+    if let Some(features) = features {
+        cmd.features(features);
+    }
+    cmd.exec()
+        .with_context(|| format!("cargo metadata failed for {}", manifest_path))
+}
+
+/// Root of the workspace containing the current directory, without
+/// resolving dependencies.
+pub fn current_workspace_root() -> Option<PathBuf> {
+    MetadataCommand::new()
+        .no_deps()
+        .exec()
+        .ok()
+        .map(|m| m.workspace_root.into_std_path_buf())
+}
+
+/// Build the package graph from the resolved dependency graph.
+///
+/// Every package in the metadata becomes a node; edges are added for the
+/// dependency kinds selected by `opts`.
+pub fn build_graph(metadata: &Metadata, opts: GraphOptions) -> DepGraph {
+    let mut graph = DepGraph::new();
+    let mut node_map: HashMap<&PackageId, NodeIndex> = HashMap::new();
+
+    for pkg in &metadata.packages {
+        let idx = graph.add_node(Package {
+            id: pkg.id.repr.clone(),
+            name: pkg.name.to_string(),
+            version: pkg.version.to_string(),
+            manifest_path: pkg.manifest_path.clone().into_std_path_buf(),
+            license: pkg.license.clone(),
+            source: pkg.source.as_ref().map(|s| s.repr.clone()),
+            owner: None,
+            workspace_member: metadata.workspace_members.contains(&pkg.id),
+            workspace: None,
+            targets: vec![],
+        });
+        node_map.insert(&pkg.id, idx);
+    }
+
+    let Some(resolve) = &metadata.resolve else {
+        return graph;
+    };
+
+    for node in &resolve.nodes {
+        let Some(&from) = node_map.get(&node.id) else {
+            continue;
+        };
+        for dep in &node.deps {
+            let Some(&to) = node_map.get(&dep.pkg) else {
+                continue;
+            };
+            let mut kinds: Vec<DependencyKind> = Vec::new();
+            let mut weight: f64 = 0.0;
+            for info in &dep.dep_kinds {
+                if let Some(w) = opts.weight(info.kind)
+                    && !kinds.contains(&info.kind)
+                {
+                    kinds.push(info.kind);
+                    weight = weight.max(w);
+                }
+            }
+            if !kinds.is_empty() {
+                graph.add_edge(
+                    from,
+                    to,
+                    Dep {
+                        kinds,
+                        weight,
+                        targets: vec![],
+                    },
+                );
+            }
+        }
+    }
+
+    graph
+}
+
+/// Drop packages not matching any `include` glob (when given) or matching
+/// an `exclude` glob, along with their edges.
+pub fn filter_packages(
+    graph: DepGraph,
+    include: &[Glob],
+    exclude: &[Glob],
+    workspace_root: &Path,
+) -> DepGraph {
+    let matches = |globs: &[Glob], pkg: &Package| {
+        let dir = pkg.manifest_path.parent().unwrap_or(&pkg.manifest_path);
+        globs
+            .iter()
+            .any(|g| g.matches(&pkg.name, dir, workspace_root))
+    };
+    graph.filter_map(
+        |_, pkg| {
+            ((include.is_empty() || matches(include, pkg)) && !matches(exclude, pkg))
+                .then(|| pkg.clone())
+        },
+        |_, dep| Some(dep.clone()),
+    )
+}
+
+/// The packages within `radius` hops of `center`, following edges in either
+/// direction, and the edges among them.
+pub fn ego_subgraph(graph: &DepGraph, center: NodeIndex, radius: usize) -> DepGraph {
+    let mut keep = vec![false; graph.node_count()];
+    keep[center.index()] = true;
+    let mut frontier = vec![center];
+    for _ in 0..radius {
+        let mut next = Vec::new();
+        for v in frontier {
+            for w in graph.neighbors_undirected(v) {
+                if !std::mem::replace(&mut keep[w.index()], true) {
+                    next.push(w);
+                }
+            }
+        }
+        frontier = next;
+    }
+    graph.filter_map(
+        |i, pkg| keep[i.index()].then(|| pkg.clone()),
+        |_, dep| Some(dep.clone()),
+    )
+}
+
+/// Find a package by `name` or `name@version`.
+pub fn find_package(graph: &DepGraph, spec: &str) -> anyhow::Result<NodeIndex> {
+    let (name, version) = match spec.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (spec, None),
+    };
+
+    let matches: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|&i| {
+            let pkg = &graph[i];
+            pkg.name == name && version.is_none_or(|v| pkg.version == v)
+        })
+        .collect();
+
+    match matches.as_slice() {
+        [] => bail!("package `{}` not found in the dependency graph", spec),
+        [idx] => Ok(*idx),
+        _ => {
+            let versions: Vec<&str> = matches.iter().map(|&i| graph[i].version.as_str()).collect();
+            bail!(
+                "package `{}` is ambiguous ({}); use `{}@<version>`",
+                spec,
+                versions.join(", "),
+                name
+            )
+        }
+    }
+}
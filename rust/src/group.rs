@@ -0,0 +1,193 @@
+//! Contracting workspace crates into directory groups (`--group-by`).
+
+use crate::graph::{DepGraph, Package};
+use petgraph::prelude::*;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Component, Path};
+use std::str::FromStr;
+
+/// How workspace crates are grouped.
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum GroupBy {
+    /// The first N directories of the crate's path under the workspace
+    /// root (`path-prefix=2` puts `crates/net/tcp` in `crates/net`).
+    PathPrefix(usize),
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some(("path-prefix", n)) => match n.parse() {
+                Ok(n) if n > 0 => Ok(Self::PathPrefix(n)),
+                _ => Err(format!("expected a positive depth, got `{}`", n)),
+            },
+            _ => Err(format!("expected `path-prefix=N`, got `{}`", s)),
+        }
+    }
+}
+
+/// Replace workspace members by one node per group. Third-party and other
+/// non-member packages are kept as they are; edges between members of the
+/// same group are dropped, parallel edges are summed.
+pub fn contract(graph: &DepGraph, root: &Path, by: GroupBy) -> DepGraph {
+    let GroupBy::PathPrefix(depth) = by;
+
+    let mut contracted = DepGraph::new();
+    let mut groups: HashMap<String, NodeIndex> = HashMap::new();
+    let map: Vec<NodeIndex> = graph
+        .node_weights()
+        .map(|pkg| {
+            if !pkg.workspace_member {
+                return contracted.add_node(pkg.clone());
+            }
+            let dir = pkg.manifest_path.parent().unwrap_or(&pkg.manifest_path);
+            let prefix: Vec<String> = dir
+                .strip_prefix(root)
+                .unwrap_or(dir)
+                .components()
+                .filter_map(|c| match c {
+                    Component::Normal(s) => Some(s.to_string_lossy().into_owned()),
+                    _ => None,
+                })
+                .take(depth)
+                .collect();
+            let label = if prefix.is_empty() {
+                ".".to_string()
+            } else {
+                prefix.join("/")
+            };
+            let idx = *groups.entry(label.clone()).or_insert_with(|| {
+                contracted.add_node(Package {
+                    id: label.clone(),
+                    name: label.clone(),
+                    version: String::new(),
+                    manifest_path: root.join(&label).join("Cargo.toml"),
+                    license: None,
+                    source: None,
+                    owner: pkg.owner.clone(),
+                    workspace_member: true,
+                    workspace: pkg.workspace.clone(),
+                    targets: vec![],
+                })
+            });
+            // A group has an owner only if all its crates agree.
+            let group = &mut contracted[idx];
+            if group.owner != pkg.owner {
+                group.owner = None;
+            }
+            idx
+        })
+        .collect();
+
+    for edge in graph.edge_references() {
+        let (from, to) = (map[edge.source().index()], map[edge.target().index()]);
+        if from == to {
+            continue;
+        }
+        let dep = edge.weight();
+        match contracted.find_edge(from, to) {
+            Some(e) => {
+                let existing = &mut contracted[e];
+                for kind in &dep.kinds {
+                    if !existing.kinds.contains(kind) {
+                        existing.kinds.push(*kind);
+                    }
+                }
+                existing.weight += dep.weight;
+            }
+            None => {
+                contracted.add_edge(from, to, dep.clone());
+            }
+        }
+    }
+    contracted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Dep;
+    use cargo_metadata::DependencyKind;
+
+    #[test]
+    fn parses_path_prefix() {
+        assert!(matches!(
+            "path-prefix=2".parse::<GroupBy>(),
+            Ok(GroupBy::PathPrefix(2))
+        ));
+        for bad in [
+            "path-prefix=0",
+            "path-prefix=x",
+            "path-prefix",
+            "prefix=2",
+            "",
+        ] {
+            assert!(bad.parse::<GroupBy>().is_err(), "{}", bad);
+        }
+    }
+
+    fn package(name: &str, dir: &str, member: bool) -> Package {
+        Package {
+            id: name.to_string(),
+            name: name.to_string(),
+            version: "0.1.0".to_string(),
+            manifest_path: Path::new("/ws").join(dir).join("Cargo.toml"),
+            license: None,
+            source: None,
+            owner: None,
+            workspace_member: member,
+            workspace: None,
+            targets: vec![],
+        }
+    }
+
+    fn dep(kind: DependencyKind, weight: f64) -> Dep {
+        Dep {
+            kinds: vec![kind],
+            weight,
+            targets: vec![],
+        }
+    }
+
+    #[test]
+    fn contract_sums_parallel_edges_and_drops_internal_ones() {
+        let mut graph = DepGraph::new();
+        let app = graph.add_node(package("app", "apps/app", true));
+        let tcp = graph.add_node(package("tcp", "crates/net/tcp", true));
+        let udp = graph.add_node(package("udp", "crates/net/udp", true));
+        let serde = graph.add_node(package("serde", "registry/serde", false));
+        graph.add_edge(app, tcp, dep(DependencyKind::Normal, 1.0));
+        graph.add_edge(app, udp, dep(DependencyKind::Development, 0.5));
+        graph.add_edge(tcp, udp, dep(DependencyKind::Normal, 1.0));
+        graph.add_edge(tcp, serde, dep(DependencyKind::Normal, 1.0));
+        graph.add_edge(udp, serde, dep(DependencyKind::Normal, 1.0));
+
+        let contracted = contract(&graph, Path::new("/ws"), GroupBy::PathPrefix(2));
+        let node = |name: &str| {
+            contracted
+                .node_indices()
+                .find(|&i| contracted[i].name == name)
+                .unwrap()
+        };
+        assert_eq!(contracted.node_count(), 3);
+        assert_eq!(contracted.edge_count(), 2);
+
+        let apps_to_net = &contracted[contracted
+            .find_edge(node("apps/app"), node("crates/net"))
+            .unwrap()];
+        assert_eq!(apps_to_net.weight, 1.5);
+        assert_eq!(
+            apps_to_net.kinds,
+            [DependencyKind::Normal, DependencyKind::Development]
+        );
+        let net_to_serde = &contracted[contracted
+            .find_edge(node("crates/net"), node("serde"))
+            .unwrap()];
+        assert_eq!(net_to_serde.weight, 2.0);
+    }
+}
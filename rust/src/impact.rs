@@ -0,0 +1,150 @@
+//! `pkgrank impact`: blast radius of a single package.
+
+use crate::centrality::{self, PageRankConfig};
+use crate::graph::{DepGraph, find_package};
+use petgraph::prelude::*;
+use petgraph::visit::{Bfs, Reversed, Walker};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ImpactRow {
+    pub name: String,
+    pub version: String,
+    pub pagerank: f64,
+    /// Connected to the inspected package by an edge of its own.
+    pub direct: bool,
+    pub workspace_member: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ImpactOut {
+    pub name: String,
+    pub version: String,
+    pub pagerank: f64,
+    /// Count of all transitive dependents, before `--top`.
+    pub total_dependents: usize,
+    /// Count of all transitive dependencies, before `--top`.
+    pub total_dependencies: usize,
+    /// Packages that (transitively) depend on this one: direct ones first,
+    /// then by descending pagerank, cut to `--top`.
+    pub dependents: Vec<ImpactRow>,
+    /// Packages this one (transitively) depends on, ordered and cut like
+    /// `dependents`.
+    pub dependencies: Vec<ImpactRow>,
+    /// Workspace members among all the dependents.
+    pub affected_workspace_members: Vec<String>,
+}
+
+pub fn impact(
+    graph: &DepGraph,
+    spec: &str,
+    top: usize,
+    config: &PageRankConfig,
+) -> anyhow::Result<ImpactOut> {
+    let target = find_package(graph, spec)?;
+    let (pagerank, _) = centrality::pagerank(graph, config);
+
+    let rows = |nodes: Vec<NodeIndex>, direct: &dyn Fn(NodeIndex) -> bool| {
+        let mut rows: Vec<ImpactRow> = nodes
+            .into_iter()
+            .filter(|&i| i != target)
+            .map(|i| ImpactRow {
+                name: graph[i].name.clone(),
+                version: graph[i].version.clone(),
+                pagerank: pagerank[i.index()],
+                direct: direct(i),
+                workspace_member: graph[i].workspace_member,
+                owner: graph[i].owner.clone(),
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            b.direct
+                .cmp(&a.direct)
+                .then_with(|| centrality::by_score_desc(a.pagerank, b.pagerank))
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.version.cmp(&b.version))
+        });
+        rows
+    };
+
+    let mut dependents = rows(
+        Bfs::new(Reversed(graph), target)
+            .iter(Reversed(graph))
+            .collect(),
+        &|i| graph.contains_edge(i, target),
+    );
+    let mut dependencies = rows(Bfs::new(graph, target).iter(graph).collect(), &|i| {
+        graph.contains_edge(target, i)
+    });
+
+    let mut affected_workspace_members: Vec<String> = dependents
+        .iter()
+        .filter(|r| r.workspace_member)
+        .map(|r| r.name.clone())
+        .collect();
+    affected_workspace_members.sort();
+    let (total_dependents, total_dependencies) = (dependents.len(), dependencies.len());
+    dependents.truncate(top);
+    dependencies.truncate(top);
+
+    Ok(ImpactOut {
+        name: graph[target].name.clone(),
+        version: graph[target].version.clone(),
+        pagerank: pagerank[target.index()],
+        total_dependents,
+        total_dependencies,
+        dependents,
+        dependencies,
+        affected_workspace_members,
+    })
+}
+
+pub fn print_text(out: &ImpactOut) {
+    println!(
+        "Impact of {} {} (pagerank {:.6}):",
+        out.name, out.version, out.pagerank
+    );
+    println!(
+        "  {} transitive dependents ({} workspace members), {} transitive dependencies",
+        out.total_dependents,
+        out.affected_workspace_members.len(),
+        out.total_dependencies
+    );
+
+    for (title, rows) in [
+        ("Dependents", &out.dependents),
+        ("Dependencies", &out.dependencies),
+    ] {
+        println!("\n{} (direct first) by Pagerank:", title);
+        println!("{:─<50}", "");
+        for (i, row) in rows.iter().enumerate() {
+            let label = format!("{} {}", row.name, row.version);
+            let marker = match (row.direct, row.workspace_member) {
+                (true, true) => " [direct, workspace]",
+                (true, false) => " [direct]",
+                (false, true) => " [workspace]",
+                (false, false) => "",
+            };
+            let owner = row.owner.as_deref().unwrap_or("");
+            let line = format!(
+                "{:3}. {:40} {:.6}{}  {}",
+                i + 1,
+                label,
+                row.pagerank,
+                marker,
+                owner
+            );
+            println!("{}", line.trim_end());
+        }
+    }
+
+    if !out.affected_workspace_members.is_empty() {
+        println!(
+            "\nAffected workspace members: {}",
+            out.affected_workspace_members.join(", ")
+        );
+    }
+}
@@ -0,0 +1,280 @@
+//! License analysis over each workspace crate's transitive dependencies.
+
+use crate::centrality::{self, PageRankConfig};
+use crate::graph::DepGraph;
+use crate::why::shortest_paths;
+use petgraph::visit::{Bfs, Walker};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum LicenseClass {
+    Permissive,
+    WeakCopyleft,
+    Copyleft,
+    Unknown,
+}
+
+const PERMISSIVE: &[&str] = &[
+    "MIT",
+    "MIT-0",
+    "Apache-2.0",
+    "BSD-2-Clause",
+    "BSD-3-Clause",
+    "0BSD",
+    "ISC",
+    "Zlib",
+    "Unlicense",
+    "CC0-1.0",
+    "BSL-1.0",
+    "Unicode-DFS-2016",
+    "Unicode-3.0",
+    "WTFPL",
+    "NCSA",
+];
+const WEAK_COPYLEFT: &[&str] = &["LGPL", "MPL", "EPL", "CDDL", "OSL"];
+const COPYLEFT: &[&str] = &["GPL", "AGPL", "SSPL", "EUPL"];
+
+/// Classify an SPDX expression. `OR` picks the most permissive choice,
+/// `AND` the most restrictive; the legacy `/` separator counts as `OR`.
+pub fn classify(expr: Option<&str>) -> LicenseClass {
+    let Some(expr) = expr else {
+        return LicenseClass::Unknown;
+    };
+    let spaced = expr
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .replace('/', " OR ");
+    let tokens: Vec<&str> = spaced.split_whitespace().collect();
+    let mut pos = 0;
+    let class = parse_or(&tokens, &mut pos);
+    if pos == tokens.len() {
+        class
+    } else {
+        LicenseClass::Unknown
+    }
+}
+
+fn parse_or(tokens: &[&str], pos: &mut usize) -> LicenseClass {
+    let mut class = parse_and(tokens, pos);
+    while tokens.get(*pos) == Some(&"OR") {
+        *pos += 1;
+        class = class.min(parse_and(tokens, pos));
+    }
+    class
+}
+
+fn parse_and(tokens: &[&str], pos: &mut usize) -> LicenseClass {
+    let mut class = parse_atom(tokens, pos);
+    while tokens.get(*pos) == Some(&"AND") {
+        *pos += 1;
+        class = class.max(parse_atom(tokens, pos));
+    }
+    class
+}
+
+fn parse_atom(tokens: &[&str], pos: &mut usize) -> LicenseClass {
+    match tokens.get(*pos) {
+        Some(&"(") => {
+            *pos += 1;
+            let class = parse_or(tokens, pos);
+            if tokens.get(*pos) == Some(&")") {
+                *pos += 1;
+                class
+            } else {
+                LicenseClass::Unknown
+            }
+        }
+        Some(id) => {
+            *pos += 1;
+            // `Apache-2.0 WITH LLVM-exception` is still Apache-2.0.
+            if tokens.get(*pos) == Some(&"WITH") {
+                *pos += 2;
+            }
+            classify_id(id)
+        }
+        None => LicenseClass::Unknown,
+    }
+}
+
+fn classify_id(id: &str) -> LicenseClass {
+    let id = id.trim_end_matches('+');
+    if PERMISSIVE.contains(&id) {
+        LicenseClass::Permissive
+    } else if WEAK_COPYLEFT.iter().any(|p| id.starts_with(p)) {
+        LicenseClass::WeakCopyleft
+    } else if COPYLEFT.iter().any(|p| id.starts_with(p)) {
+        LicenseClass::Copyleft
+    } else {
+        LicenseClass::Unknown
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FlaggedDep {
+    pub name: String,
+    pub version: String,
+    pub license: Option<String>,
+    pub class: LicenseClass,
+    pub pagerank: f64,
+    /// Shortest path from the workspace crate to this dependency.
+    pub path: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CrateLicenses {
+    pub name: String,
+    pub version: String,
+    /// License expression -> number of transitive dependencies using it.
+    pub licenses: BTreeMap<String, usize>,
+    /// Copyleft and unknown licenses, by descending pagerank.
+    pub flagged: Vec<FlaggedDep>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LicenseReport {
+    /// License expression -> number of packages in the graph using it.
+    pub totals: BTreeMap<String, usize>,
+    pub crates: Vec<CrateLicenses>,
+}
+
+const UNKNOWN: &str = "(none)";
+
+pub fn license_report(graph: &DepGraph, config: &PageRankConfig) -> LicenseReport {
+    let (pagerank, _) = centrality::pagerank(graph, config);
+
+    let mut totals = BTreeMap::new();
+    for i in graph.node_indices().filter(|&i| !graph[i].workspace_member) {
+        let expr = graph[i].license.as_deref().unwrap_or(UNKNOWN);
+        *totals.entry(expr.to_string()).or_insert(0) += 1;
+    }
+
+    let mut crates: Vec<CrateLicenses> = graph
+        .node_indices()
+        .filter(|&i| graph[i].workspace_member)
+        .map(|member| {
+            let mut licenses = BTreeMap::new();
+            let mut flagged = Vec::new();
+            for i in Bfs::new(graph, member).iter(graph) {
+                let pkg = &graph[i];
+                if pkg.workspace_member {
+                    continue;
+                }
+                let expr = pkg.license.as_deref().unwrap_or(UNKNOWN);
+                *licenses.entry(expr.to_string()).or_insert(0) += 1;
+
+                let class = classify(pkg.license.as_deref());
+                if class != LicenseClass::Permissive {
+                    let path = shortest_paths(graph, member, i, 1)
+                        .into_iter()
+                        .next()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|j| graph[j].name.clone())
+                        .collect();
+                    flagged.push(FlaggedDep {
+                        name: pkg.name.clone(),
+                        version: pkg.version.clone(),
+                        license: pkg.license.clone(),
+                        class,
+                        pagerank: pagerank[i.index()],
+                        path,
+                    });
+                }
+            }
+            flagged.sort_by(|a, b| {
+                centrality::by_score_desc(a.pagerank, b.pagerank)
+                    .then_with(|| a.name.cmp(&b.name))
+                    .then_with(|| a.version.cmp(&b.version))
+            });
+            CrateLicenses {
+                name: graph[member].name.clone(),
+                version: graph[member].version.clone(),
+                licenses,
+                flagged,
+            }
+        })
+        .collect();
+    crates.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version)));
+
+    LicenseReport { totals, crates }
+}
+
+pub fn print_text(report: &LicenseReport) {
+    println!("\nLicenses:");
+    println!("{:─<50}", "");
+    let mut totals: Vec<_> = report.totals.iter().collect();
+    totals.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    for (expr, count) in totals {
+        println!("  {:5} {}", count, expr);
+    }
+
+    for krate in report.crates.iter().filter(|c| !c.flagged.is_empty()) {
+        println!(
+            "\nFlagged dependencies of {} {}:",
+            krate.name, krate.version
+        );
+        for dep in &krate.flagged {
+            println!(
+                "  {} {} [{}] {:?} pagerank {:.6}",
+                dep.name,
+                dep.version,
+                dep.license.as_deref().unwrap_or(UNKNOWN),
+                dep.class,
+                dep.pagerank
+            );
+            println!("    via {}", dep.path.join(" -> "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_slash_is_or() {
+        assert_eq!(classify(Some("MIT/Apache-2.0")), LicenseClass::Permissive);
+        assert_eq!(classify(Some("GPL-3.0/MIT")), LicenseClass::Permissive);
+    }
+
+    #[test]
+    fn and_takes_the_most_restrictive_side() {
+        assert_eq!(
+            classify(Some("(MIT OR Apache-2.0) AND BSD-3-Clause")),
+            LicenseClass::Permissive
+        );
+        assert_eq!(
+            classify(Some("(MIT OR Apache-2.0) AND MPL-2.0")),
+            LicenseClass::WeakCopyleft
+        );
+        assert_eq!(
+            classify(Some("MIT AND (LGPL-2.1 OR GPL-3.0)")),
+            LicenseClass::WeakCopyleft
+        );
+    }
+
+    #[test]
+    fn with_exception_keeps_the_base_license() {
+        assert_eq!(
+            classify(Some("Apache-2.0 WITH LLVM-exception")),
+            LicenseClass::Permissive
+        );
+        assert_eq!(
+            classify(Some("GPL-2.0-or-later WITH Classpath-exception-2.0 OR MIT")),
+            LicenseClass::Permissive
+        );
+        assert_eq!(classify(Some("Apache-2.0 WITH")), LicenseClass::Unknown);
+    }
+
+    #[test]
+    fn missing_or_malformed_is_unknown() {
+        assert_eq!(classify(None), LicenseClass::Unknown);
+        assert_eq!(classify(Some("(MIT OR Apache-2.0")), LicenseClass::Unknown);
+        assert_eq!(classify(Some("MIT Apache-2.0")), LicenseClass::Unknown);
+        assert_eq!(classify(Some("Proprietary")), LicenseClass::Unknown);
+        assert_eq!(classify(Some("AGPL-3.0+")), LicenseClass::Copyleft);
+    }
+}
@@ -2,198 +2,1480 @@
 //!
 //! Computes PageRank and other centrality metrics over Cargo dependency graphs.
 
-use cargo_metadata::{MetadataCommand, PackageId};
-use clap::{Parser, ValueEnum};
+mod analyze;
+mod artifacts;
+mod centrality;
+mod cochange;
+mod codeowners;
+mod compare;
+mod digest;
+mod dupes;
+mod features;
+mod fixture;
+mod gateways;
+mod gc;
+mod git;
+mod glob;
+mod graph;
+mod group;
+mod impact;
+mod license;
+mod modules;
+mod nested;
+mod orphans;
+mod owners;
+mod ownership;
+mod sbom;
+mod schema;
+mod selfcheck;
+mod snapshot;
+mod targets;
+mod time;
+mod truncate;
+mod watch;
+mod why;
+
+use analyze::{AnalyzeOut, AnalyzeRow, Metric, Phase, Progress};
+use anyhow::{Context, bail};
+use cargo_metadata::{CargoOpt, Metadata};
+use centrality::PageRankConfig;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use codeowners::CodeOwners;
+use glob::Glob;
+use graph::{DepGraph, GraphOptions, build_graph, find_package, load_metadata};
+use group::GroupBy;
 use petgraph::prelude::*;
-use std::collections::HashMap;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 #[derive(Parser, Debug)]
 #[command(name = "pkgrank")]
 #[command(about = "Cargo dependency graph centrality analysis")]
-struct Args {
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    analyze: AnalyzeArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Rank packages by centrality (the default when no subcommand is given)
+    Analyze(AnalyzeArgs),
+    /// Show the shortest dependency paths between two packages
+    Why(WhyArgs),
+    /// Show the transitive dependents and dependencies of a package
+    Impact(ImpactArgs),
+    /// List workspace crates with no workspace dependents or dependencies
+    Orphans(OrphansArgs),
+    /// Show which packages and edges enabling features pulls into the graph
+    FeaturesImpact(FeaturesImpactArgs),
+    /// List third-party packages resolved at more than one version
+    Dupes(DupesArgs),
+    /// Print a compact ranking digest, with changes since the previous digest
+    Digest(DigestArgs),
+    /// Export a CycloneDX or SPDX SBOM annotated with centrality scores
+    Sbom(SbomArgs),
+    /// List workspace crates that are the only way into a third-party subtree
+    Gateways(GatewaysArgs),
+    /// Roll up score mass, third-party surface, and staleness per CODEOWNERS owner
+    Owners(OwnersArgs),
+    /// Rank a crate's modules by how the rest of the crate uses them
+    Modules(ModulesArgs),
+    /// Show git author concentration (bus factor) per workspace crate
+    Ownership(OwnershipArgs),
+    /// Find crates that change together in git history but have no dependency edge
+    CoChange(CoChangeArgs),
+    /// Re-run analyze whenever a Cargo.toml or Cargo.lock changes
+    Watch(WatchArgs),
+    /// Rank pkgrank's own dependencies and compare against the committed golden result
+    Selfcheck(SelfcheckArgs),
+    /// Write a synthetic workspace with a known dependency structure
+    GenFixture(GenFixtureArgs),
+    /// Copy the current artifacts into `<out>/runs/<label>/` with a manifest
+    Snapshot(SnapshotArgs),
+    /// Diff the artifacts of two runs (snapshots or --out directories)
+    CompareRuns(CompareRunsArgs),
+    /// Remove old snapshots by age and total size
+    Gc(GcArgs),
+    /// Print the JSON Schema of a command's --json output
+    Schema(SchemaArgs),
+    /// Inspect the artifact files pkgrank writes
+    #[command(subcommand)]
+    Artifacts(ArtifactsCommand),
+}
+
+#[derive(Args, Debug)]
+struct SchemaArgs {
+    /// Payload to describe
+    #[arg(value_enum)]
+    payload: schema::Payload,
+}
+
+#[derive(Args, Debug)]
+struct GenFixtureArgs {
+    /// Directory to create (must be empty or missing)
+    dir: PathBuf,
+
+    /// Number of crates
+    #[arg(long, default_value = "10")]
+    crates: usize,
+
+    /// Number of dependency edges (capped at what an acyclic graph allows)
+    #[arg(long, default_value = "20")]
+    edges: usize,
+
+    /// Where the crates go
+    #[arg(long, value_enum, default_value = "workspace")]
+    layout: fixture::Layout,
+
+    /// Seed for choosing edges; the same seed gives the same fixture
+    #[arg(long, default_value = "0")]
+    seed: u64,
+}
+
+#[derive(Args, Debug)]
+struct SnapshotArgs {
+    /// Artifact directory to snapshot (as passed to analyze/modules --out)
+    /// [default: `.pkgrank` under the workspace root]
+    #[arg(long, value_name = "DIR")]
+    out: Option<PathBuf>,
+
+    /// Snapshot name [default: `git describe --always --dirty` plus the UTC time]
+    #[arg(long)]
+    label: Option<String>,
+
+    /// Print the manifest as JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct CompareRunsArgs {
+    /// Artifact directory of the earlier run
+    #[arg(long)]
+    old: PathBuf,
+
+    /// Artifact directory of the later run
+    #[arg(long)]
+    new: PathBuf,
+
+    /// Only report rank changes of at least this many places
+    #[arg(long, default_value = "1")]
+    min_move: usize,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    format: CompareFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CompareFormat {
+    Text,
+    Json,
+    Markdown,
+}
+
+#[derive(Args, Debug)]
+#[command(group(clap::ArgGroup::new("retention").required(true).multiple(true)))]
+struct GcArgs {
+    /// Artifact directory whose `runs/` to prune [default: `.pkgrank`
+    /// under the workspace root]
+    #[arg(long, value_name = "DIR")]
+    out: Option<PathBuf>,
+
+    /// Remove snapshots older than this many days
+    #[arg(long, group = "retention")]
+    max_age_days: Option<u64>,
+
+    /// Then remove the oldest snapshots until the rest fit in this many bytes
+    #[arg(long, group = "retention")]
+    max_bytes: Option<u64>,
+
+    /// Report what would be removed without removing anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct SelfcheckArgs {
+    /// Rewrite the golden file from this run instead of comparing
+    #[arg(long)]
+    bless: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum ArtifactsCommand {
+    /// List known artifacts and, with --dir, which are present
+    List(ArtifactsListArgs),
+}
+
+#[derive(Args, Debug)]
+struct ArtifactsListArgs {
+    /// Artifact directory to check (e.g. an analyze --out dir or `.pkgrank`)
+    #[arg(long)]
+    dir: Option<PathBuf>,
+
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct GraphArgs {
     /// Path to Cargo.toml or directory
     #[arg(default_value = ".")]
     path: String,
 
+    /// Include dev-dependencies
+    #[arg(long)]
+    dev: bool,
+
+    /// Include build-dependencies
+    #[arg(long)]
+    build: bool,
+
+    /// Edge weight for normal dependencies (0 drops them)
+    #[arg(long, default_value_t = 1.0, value_parser = parse_weight)]
+    weight_normal: f64,
+
+    /// Edge weight for dev-dependencies (implies --dev)
+    #[arg(long, value_parser = parse_weight)]
+    weight_dev: Option<f64>,
+
+    /// Edge weight for build-dependencies (implies --build)
+    #[arg(long, value_parser = parse_weight)]
+    weight_build: Option<f64>,
+
+    /// Only resolve dependencies that build for this target triple
+    #[arg(long, value_name = "TRIPLE")]
+    filter_platform: Option<String>,
+
+    /// Resolve for each of these target triples and merge the graphs
+    #[arg(
+        long,
+        value_name = "TRIPLE,...",
+        value_delimiter = ',',
+        conflicts_with = "filter_platform"
+    )]
+    targets: Vec<String>,
+
+    /// Also analyze workspaces nested under this one (directories with
+    /// their own `[workspace]`) and merge them into the graph
+    #[arg(long, conflicts_with = "targets")]
+    nested: bool,
+
+    /// Merge in the graph of another workspace (repeatable); shared
+    /// packages become one node
+    #[arg(long, value_name = "PATH", conflicts_with = "targets")]
+    merge: Vec<String>,
+
+    /// File listing more workspaces to merge, one path per line (relative
+    /// to the file; `#` starts a comment)
+    #[arg(long, value_name = "FILE", conflicts_with = "targets")]
+    merge_list: Option<PathBuf>,
+
+    /// CODEOWNERS file for crate owners [default: .github/CODEOWNERS,
+    /// CODEOWNERS or docs/CODEOWNERS in the repository]
+    #[arg(long, value_name = "FILE")]
+    codeowners: Option<PathBuf>,
+
+    /// Keep only packages whose name (or, with a `/`, manifest directory)
+    /// matches this glob (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Drop packages whose name (or, with a `/`, manifest directory)
+    /// matches this glob (repeatable); a plain crate name drops just that
+    /// crate, so this also serves as `--exclude-crate`
+    #[arg(long, visible_alias = "exclude-crate", value_name = "GLOB")]
+    exclude: Vec<String>,
+}
+
+/// Edge weights must be finite and non-negative for PageRank to mean anything.
+fn parse_weight(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(w) if w.is_finite() && w >= 0.0 => Ok(w),
+        Ok(_) => Err(format!("expected a finite weight >= 0, got `{}`", s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// A tolerance of zero or less never converges early.
+fn parse_tolerance(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(t) if t.is_finite() && t > 0.0 => Ok(t),
+        Ok(_) => Err(format!("expected a finite tolerance > 0, got `{}`", s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_positive(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        Ok(_) => Err("expected at least 1".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+impl GraphArgs {
+    fn metadata(&self) -> anyhow::Result<Metadata> {
+        load_metadata(&self.path, self.filter_platform.as_deref(), None)
+    }
+
+    /// Load metadata and build the graph, with owners attached.
+    ///
+    /// With `--targets`, the graph is the union of one graph per target and
+    /// the returned metadata is the first target's. With `--nested` or
+    /// `--merge`, other workspaces' graphs are merged into the root's.
+    fn load(&self) -> anyhow::Result<(Metadata, DepGraph)> {
+        if self.targets.is_empty() {
+            let metadata = self.metadata()?;
+            let merge = self.merge_paths()?;
+            if !self.nested && merge.is_empty() {
+                let graph = self.graph(&metadata)?;
+                return Ok((metadata, graph));
+            }
+            let root = metadata.workspace_root.as_std_path();
+            let mut dirs: Vec<String> = Vec::new();
+            if self.nested {
+                dirs.extend(
+                    nested::find(root)
+                        .iter()
+                        .map(|d| d.to_string_lossy().into_owned()),
+                );
+            }
+            dirs.extend(merge);
+            let mut graphs: Vec<(String, DepGraph)> = Vec::new();
+            for dir in dirs {
+                let other = load_metadata(&dir, self.filter_platform.as_deref(), None)?;
+                let label = nested::label(root, other.workspace_root.as_std_path());
+                if other.workspace_root == metadata.workspace_root
+                    || graphs.iter().any(|(l, _)| *l == label)
+                {
+                    continue;
+                }
+                graphs.push((label, build_graph(&other, self.options())));
+            }
+            let graph = nested::merge(build_graph(&metadata, self.options()), graphs);
+            let graph = self.finish(graph, &metadata)?;
+            return Ok((metadata, graph));
+        }
+
+        let mut graphs = Vec::new();
+        let mut first = None;
+        for triple in &self.targets() {
+            let metadata = load_metadata(&self.path, Some(triple), None)?;
+            graphs.push((triple.clone(), build_graph(&metadata, self.options())));
+            first.get_or_insert(metadata);
+        }
+        let metadata = first.expect("--targets is non-empty");
+        let graph = self.finish(targets::merge(graphs), &metadata)?;
+        Ok((metadata, graph))
+    }
+
+    /// `--targets` in the order given, without repeats.
+    fn targets(&self) -> Vec<String> {
+        let mut seen = BTreeSet::new();
+        self.targets
+            .iter()
+            .filter(|t| seen.insert(t.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// `--merge` paths followed by the entries of `--merge-list`.
+    fn merge_paths(&self) -> anyhow::Result<Vec<String>> {
+        let mut paths = self.merge.clone();
+        if let Some(list) = &self.merge_list {
+            let text = std::fs::read_to_string(list)
+                .with_context(|| format!("reading {}", list.display()))?;
+            let base = list.parent().unwrap_or(Path::new("."));
+            paths.extend(
+                text.lines()
+                    .map(|line| line.split('#').next().unwrap_or("").trim())
+                    .filter(|line| !line.is_empty())
+                    .map(|line| base.join(line).to_string_lossy().into_owned()),
+            );
+        }
+        Ok(paths)
+    }
+
+    fn graph(&self, metadata: &Metadata) -> anyhow::Result<DepGraph> {
+        self.finish(build_graph(metadata, self.options()), metadata)
+    }
+
+    /// Apply package filters and attach owners.
+    fn finish(&self, mut graph: DepGraph, metadata: &Metadata) -> anyhow::Result<DepGraph> {
+        if !self.include.is_empty() || !self.exclude.is_empty() {
+            let globs = |patterns: &[String]| -> Vec<Glob> {
+                patterns.iter().map(|p| Glob::new(p)).collect()
+            };
+            graph = graph::filter_packages(
+                graph,
+                &globs(&self.include),
+                &globs(&self.exclude),
+                metadata.workspace_root.as_std_path(),
+            );
+        }
+        if let Some(owners) = CodeOwners::discover(
+            metadata.workspace_root.as_std_path(),
+            self.codeowners.as_deref(),
+        )? {
+            codeowners::assign_owners(&mut graph, &owners);
+        }
+        Ok(graph)
+    }
+
+    fn options(&self) -> GraphOptions {
+        let kind = |enabled: bool, weight: Option<f64>| weight.or(enabled.then_some(1.0));
+        GraphOptions {
+            normal: Some(self.weight_normal),
+            dev: kind(self.dev, self.weight_dev),
+            build: kind(self.build, self.weight_build),
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct AnalyzeArgs {
+    #[command(flatten)]
+    graph: GraphArgs,
+
     /// Centrality metric
     #[arg(short, long, value_enum, default_value = "pagerank")]
     metric: Metric,
 
+    /// Teleport set for `--metric ppr` (`name` or `name@version`, repeatable)
+    #[arg(long = "seed", value_name = "CRATE")]
+    seeds: Vec<String>,
+
     /// Number of top packages to show
     #[arg(short = 'n', long, default_value = "10")]
     top: usize,
 
-    /// Include dev-dependencies
-    #[arg(long)]
-    dev: bool,
+    /// Measure git churn of path packages over this many days
+    #[arg(long, value_name = "DAYS")]
+    churn_window: Option<u64>,
 
-    /// Include build-dependencies
+    /// Show how many hops away each workspace crate's third-party deps are
     #[arg(long)]
-    build: bool,
+    depths: bool,
 
     /// Show only workspace members
     #[arg(long)]
     workspace_only: bool,
+
+    /// Score only the packages within --radius hops of this one
+    /// (`name` or `name@version`), following edges in both directions
+    #[arg(long, value_name = "CRATE")]
+    focus: Option<String>,
+
+    /// Hops from --focus to include
+    #[arg(long, default_value = "2", requires = "focus")]
+    radius: usize,
+
+    /// Rank groups of workspace crates instead of single crates
+    /// (`path-prefix=N`: by their first N directories under the root)
+    #[arg(long, value_name = "path-prefix=N")]
+    group_by: Option<GroupBy>,
+
+    /// Add a license report over each workspace crate's dependencies
+    #[arg(long)]
+    licenses: bool,
+
+    #[command(flatten)]
+    pagerank: PageRankArgs,
+
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+
+    /// Also write the JSON payload to `<DIR>/analyze.json`, the edges
+    /// between workspace crates to `<DIR>/workspace_graph.json`, with
+    /// --licenses the license report to `<DIR>/licenses.json`, and with
+    /// `--metric foundation` the ranking to `<DIR>/foundation.json` too
+    #[arg(long, value_name = "DIR")]
+    out: Option<PathBuf>,
+
+    /// Print progress (phases, PageRank iterations) and then the result as
+    /// NDJSON events
+    #[arg(long, conflicts_with = "json")]
+    stream: bool,
+}
+
+/// PageRank tuning, shared by the commands that expose it.
+#[derive(Args, Debug)]
+struct PageRankArgs {
+    /// PageRank damping factor
+    #[arg(long, default_value_t = PageRankConfig::default().damping)]
+    damping: f64,
+
+    /// Maximum PageRank iterations
+    #[arg(long, default_value_t = PageRankConfig::default().max_iter, value_parser = parse_positive)]
+    max_iter: usize,
+
+    /// PageRank convergence tolerance (L1 distance between iterates)
+    #[arg(long, default_value_t = PageRankConfig::default().tolerance, value_parser = parse_tolerance)]
+    tolerance: f64,
+}
+
+impl PageRankArgs {
+    fn config(&self) -> anyhow::Result<PageRankConfig> {
+        if !(0.0..1.0).contains(&self.damping) {
+            bail!("--damping must be in [0, 1), got {}", self.damping);
+        }
+        Ok(PageRankConfig {
+            damping: self.damping,
+            max_iter: self.max_iter,
+            tolerance: self.tolerance,
+        })
+    }
+}
+
+#[derive(Args, Debug)]
+struct WhyArgs {
+    #[command(flatten)]
+    graph: GraphArgs,
+
+    #[command(flatten)]
+    pagerank: PageRankArgs,
+
+    /// Dependent package (`name` or `name@version`)
+    #[arg(long)]
+    from: String,
+
+    /// Dependency package (`name` or `name@version`)
+    #[arg(long)]
+    to: String,
+
+    /// Maximum number of shortest paths to show
+    #[arg(long, default_value = "5", value_parser = parse_positive)]
+    max_paths: usize,
+
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct ImpactArgs {
+    /// Package to inspect (`name` or `name@version`)
+    #[arg(value_name = "CRATE")]
+    krate: String,
+
+    #[command(flatten)]
+    graph: GraphArgs,
+
+    #[command(flatten)]
+    pagerank: PageRankArgs,
+
+    /// Number of dependents and dependencies to show
+    #[arg(short = 'n', long, default_value = "10")]
+    top: usize,
+
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct OrphansArgs {
+    #[command(flatten)]
+    graph: GraphArgs,
+
+    /// Only report crates with no git commits in this many days
+    #[arg(long, value_name = "DAYS")]
+    stale_days: Option<u64>,
+
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct GatewaysArgs {
+    #[command(flatten)]
+    graph: GraphArgs,
+
+    #[command(flatten)]
+    pagerank: PageRankArgs,
+
+    /// Only report crates gating at least this many third-party packages
+    #[arg(long, default_value = "1")]
+    min_gated: usize,
+
+    /// Number of crates to show
+    #[arg(short = 'n', long, default_value = "20")]
+    top: usize,
+
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct FeaturesImpactArgs {
+    #[command(flatten)]
+    graph: GraphArgs,
+
+    #[command(flatten)]
+    pagerank: PageRankArgs,
+
+    /// Features to enable, compared against default features
+    #[arg(long, value_delimiter = ',', required_unless_present = "all_features")]
+    features: Vec<String>,
+
+    /// Compare against all features enabled
+    #[arg(long, conflicts_with = "features")]
+    all_features: bool,
+
+    /// Number of largest score changes to show
+    #[arg(short = 'n', long, default_value = "10")]
+    top: usize,
+
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct DupesArgs {
+    #[command(flatten)]
+    graph: GraphArgs,
+
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct DigestArgs {
+    #[command(flatten)]
+    graph: GraphArgs,
+
+    #[command(flatten)]
+    pagerank: PageRankArgs,
+
+    /// Compare against the previously recorded digest
+    #[arg(long)]
+    since_last: bool,
+
+    /// Digest state file [default: <workspace root>/.pkgrank/digest.json]
+    #[arg(long)]
+    state: Option<PathBuf>,
+
+    /// Do not record this run as the new high-water mark
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    format: DigestFormat,
+
+    /// Number of top packages to show
+    #[arg(short = 'n', long, default_value = "10")]
+    top: usize,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
-enum Metric {
-    Pagerank,
-    Indegree,
-    Outdegree,
-    Betweenness,
+enum DigestFormat {
+    Text,
+    Markdown,
+}
+
+#[derive(Args, Debug)]
+struct SbomArgs {
+    #[command(flatten)]
+    graph: GraphArgs,
+
+    #[command(flatten)]
+    pagerank: PageRankArgs,
+
+    /// SBOM format
+    #[arg(long, value_enum, default_value = "cyclonedx")]
+    format: sbom::SbomFormat,
+
+    #[command(flatten)]
+    caps: CapsArgs,
+}
+
+/// Size limits for exported graphs, applied after scoring.
+#[derive(Args, Debug)]
+struct CapsArgs {
+    /// Export only the N highest-scored nodes (and the edges among them)
+    #[arg(long, value_name = "N")]
+    max_nodes: Option<usize>,
+
+    /// Export at most N edges, keeping the heaviest
+    #[arg(long, value_name = "N")]
+    max_edges: Option<usize>,
+}
+
+impl CapsArgs {
+    fn caps(&self) -> truncate::Caps {
+        truncate::Caps {
+            max_nodes: self.max_nodes,
+            max_edges: self.max_edges,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct OwnersArgs {
+    #[command(flatten)]
+    graph: GraphArgs,
+
+    #[command(flatten)]
+    pagerank: PageRankArgs,
+
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+
+    /// Also write the JSON payload to `<DIR>/owners.json`
+    #[arg(long, value_name = "DIR")]
+    out: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct ModulesArgs {
+    /// Path to Cargo.toml or directory
+    #[arg(default_value = ".")]
+    path: String,
+
+    /// Workspace package to analyze [default: the root package]
+    #[arg(short, long)]
+    package: Option<String>,
+
+    /// Analyze this binary target instead of the library
+    #[arg(long)]
+    bin: Option<String>,
+
+    /// Merge every workspace member into one module graph, resolving
+    /// paths into other members, and rank it as a whole
+    #[arg(long, conflicts_with_all = ["package", "bin"])]
+    workspace: bool,
+
+    /// How the module graph is built
+    #[arg(long, value_enum, default_value = "syn")]
+    backend: modules::Backend,
+
+    /// Rank by pagerank per 1000 lines of code instead of raw pagerank
+    #[arg(long)]
+    per_kloc: bool,
+
+    /// List groups of mutually dependent modules and the items behind each edge
+    #[arg(long)]
+    report_cycles: bool,
+
+    /// Count the items each module exposes to the rest of the crate
+    #[arg(long)]
+    api_surface: bool,
+
+    /// List modules and items nothing else uses, and files no `mod` reaches
+    #[arg(long)]
+    report_unused: bool,
+
+    /// Number of top modules to show
+    #[arg(short = 'n', long, default_value = "20")]
+    top: usize,
+
+    #[command(flatten)]
+    pagerank: PageRankArgs,
+
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+
+    /// Write the scored graph (nodes and weighted edges) to this file, as
+    /// DOT if it ends in `.dot` and JSON otherwise
+    #[arg(long, value_name = "PATH")]
+    emit_graph: Option<PathBuf>,
+
+    #[command(flatten)]
+    caps: CapsArgs,
+
+    /// Print per-phase timings to stderr
+    #[arg(long)]
+    stats: bool,
+
+    /// Also write the JSON payload to `<DIR>/modules.json`
+    #[arg(long, value_name = "DIR")]
+    out: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct OwnershipArgs {
+    #[command(flatten)]
+    graph: GraphArgs,
+
+    #[command(flatten)]
+    pagerank: PageRankArgs,
+
+    /// Only count commits from the last DAYS days [default: all history]
+    #[arg(long)]
+    days: Option<u64>,
+
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct CoChangeArgs {
+    #[command(flatten)]
+    graph: GraphArgs,
+
+    /// History window in days
+    #[arg(long, default_value = "365")]
+    days: u64,
+
+    /// Minimum number of shared commits for a pair to be reported
+    #[arg(long, default_value = "3")]
+    min_commits: usize,
+
+    /// Ignore commits touching more files than this
+    #[arg(long, default_value = "50")]
+    max_files: usize,
+
+    /// Number of pairs to show
+    #[arg(short = 'n', long, default_value = "20")]
+    top: usize,
+
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct WatchArgs {
+    #[command(flatten)]
+    analyze: AnalyzeArgs,
+
+    /// Wait this long for further changes before re-running
+    #[arg(long, default_value = "500")]
+    debounce_ms: u64,
 }
 
 fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
+    let cli = Cli::parse();
 
-    let manifest_path = if args.path.ends_with("Cargo.toml") {
-        args.path.clone()
-    } else {
-        format!("{}/Cargo.toml", args.path)
+    match cli.command {
+        None => run_analyze(&cli.analyze),
+        Some(Command::Analyze(args)) => run_analyze(&args),
+        Some(Command::Why(args)) => run_why(&args),
+        Some(Command::Impact(args)) => run_impact(&args),
+        Some(Command::Orphans(args)) => run_orphans(&args),
+        Some(Command::Gateways(args)) => run_gateways(&args),
+        Some(Command::FeaturesImpact(args)) => run_features_impact(&args),
+        Some(Command::Dupes(args)) => run_dupes(&args),
+        Some(Command::Digest(args)) => run_digest(&args),
+        Some(Command::Sbom(args)) => run_sbom(&args),
+        Some(Command::Owners(args)) => run_owners(&args),
+        Some(Command::Modules(args)) => run_modules(&args),
+        Some(Command::Ownership(args)) => run_ownership(&args),
+        Some(Command::CoChange(args)) => run_co_change(&args),
+        Some(Command::Watch(args)) => run_watch(&args),
+        Some(Command::Selfcheck(args)) => run_selfcheck(&args),
+        Some(Command::GenFixture(args)) => run_gen_fixture(&args),
+        Some(Command::Snapshot(args)) => run_snapshot(&args),
+        Some(Command::CompareRuns(args)) => run_compare_runs(&args),
+        Some(Command::Gc(args)) => run_gc(&args),
+        Some(Command::Schema(args)) => run_schema(&args),
+        Some(Command::Artifacts(ArtifactsCommand::List(args))) => run_artifacts_list(&args),
+    }
+}
+
+fn run_analyze(args: &AnalyzeArgs) -> anyhow::Result<()> {
+    let start = Instant::now();
+    let emit = |event: Progress| {
+        if args.stream {
+            analyze::emit(start, event);
+        }
     };
 
-    let metadata = MetadataCommand::new()
-        .manifest_path(&manifest_path)
-        .exec()?;
-
-    let mut graph: DiGraph<&str, ()> = DiGraph::new();
-    let mut node_map: HashMap<&PackageId, NodeIndex> = HashMap::new();
-
-    for pkg in &metadata.packages {
-        let idx = graph.add_node(&pkg.name);
-        node_map.insert(&pkg.id, idx);
-    }
-
-    for pkg in &metadata.packages {
-        let pkg_idx = node_map[&pkg.id];
-        for dep in &pkg.dependencies {
-            if let Some(dep_pkg) = metadata.packages.iter().find(|p| p.name == dep.name) {
-                let include = match dep.kind {
-                    cargo_metadata::DependencyKind::Normal => true,
-                    cargo_metadata::DependencyKind::Development => args.dev,
-                    cargo_metadata::DependencyKind::Build => args.build,
-                    _ => false,
-                };
-                if include {
-                    let dep_idx = node_map[&dep_pkg.id];
-                    graph.add_edge(pkg_idx, dep_idx, ());
-                }
+    let (metadata, graph) = args.graph.load()?;
+    emit(Progress::Phase {
+        phase: Phase::Loaded,
+    });
+    let out = analyze_graph(args, &metadata, &graph, &mut |event| emit(event))?;
+    for warning in &out.warnings {
+        eprintln!("warning: {}", warning);
+    }
+
+    if let Some(dir) = &args.out {
+        let root = metadata.workspace_root.as_std_path();
+        artifacts::ANALYZE.write(dir, Some(root), &out)?;
+        if matches!(args.metric, Metric::Foundation) {
+            artifacts::FOUNDATION.write(dir, Some(root), &out)?;
+        }
+        artifacts::WORKSPACE_GRAPH.write(dir, Some(root), &analyze::workspace_graph(&graph))?;
+        match &out.licenses {
+            Some(licenses) => {
+                artifacts::LICENSES.write(dir, Some(root), licenses)?;
+            }
+            // A report from an earlier run would no longer match analyze.json.
+            None => {
+                let _ = std::fs::remove_file(artifacts::LICENSES.path(dir));
             }
         }
     }
 
-    let scores: Vec<(&str, f64)> = match args.metric {
-        Metric::Pagerank => pagerank(&graph),
-        Metric::Indegree => degree_centrality(&graph, Direction::Incoming),
-        Metric::Outdegree => degree_centrality(&graph, Direction::Outgoing),
-        Metric::Betweenness => betweenness_centrality(&graph),
+    if args.stream {
+        emit(Progress::Result(&out));
+    } else if args.json {
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        analyze::print_text(&out, args.top);
+    }
+
+    Ok(())
+}
+
+fn analyze_graph(
+    args: &AnalyzeArgs,
+    metadata: &Metadata,
+    graph: &DepGraph,
+    progress: &mut dyn FnMut(Progress),
+) -> anyhow::Result<AnalyzeOut> {
+    let config = args.pagerank.config()?;
+    let mut observe = |iteration, diff| progress(Progress::Iteration { iteration, diff });
+
+    let focused;
+    let graph = match &args.focus {
+        Some(spec) => {
+            focused = graph::ego_subgraph(graph, find_package(graph, spec)?, args.radius);
+            &focused
+        }
+        None => graph,
     };
 
-    let workspace_members: std::collections::HashSet<_> = metadata
-        .workspace_members
-        .iter()
-        .filter_map(|id| metadata.packages.iter().find(|p| &p.id == id))
-        .map(|p| p.name.as_str())
+    let grouped;
+    let graph = match args.group_by {
+        Some(by) => {
+            grouped = group::contract(graph, metadata.workspace_root.as_std_path(), by);
+            &grouped
+        }
+        None => graph,
+    };
+
+    let (scores, convergence) = match args.metric {
+        Metric::Pagerank => {
+            let (scores, convergence) = centrality::pagerank_observed(graph, &config, &mut observe);
+            (scores, Some(convergence))
+        }
+        Metric::Ppr => {
+            if args.seeds.is_empty() {
+                bail!("--metric ppr requires at least one --seed");
+            }
+            let seeds = args
+                .seeds
+                .iter()
+                .map(|s| find_package(graph, s))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let (scores, convergence) =
+                centrality::personalized_pagerank_observed(graph, &seeds, &config, &mut observe);
+            (scores, Some(convergence))
+        }
+        Metric::Indegree => (
+            centrality::degree_centrality(graph, Direction::Incoming),
+            None,
+        ),
+        Metric::Outdegree => (
+            centrality::degree_centrality(graph, Direction::Outgoing),
+            None,
+        ),
+        Metric::Betweenness => (centrality::betweenness_centrality(graph), None),
+        Metric::Churn => {
+            if args.churn_window.is_none() {
+                bail!("--metric churn requires --churn-window");
+            }
+            let (scores, convergence) = centrality::pagerank_observed(graph, &config, &mut observe);
+            (scores, Some(convergence))
+        }
+        Metric::Foundation => (analyze::foundation(graph), None),
+    };
+    progress(Progress::Phase {
+        phase: Phase::Scored,
+    });
+
+    let churn_window = match args.metric {
+        Metric::Foundation => args.churn_window.or(Some(analyze::FOUNDATION_CHURN_DAYS)),
+        _ => args.churn_window,
+    };
+    let churn: Vec<Option<git::Churn>> = graph
+        .node_weights()
+        .map(|pkg| match (churn_window, &pkg.source) {
+            (Some(days), None) => git::churn(pkg.manifest_path.parent()?, days),
+            _ => None,
+        })
         .collect();
+    let scores: Vec<f64> = match args.metric {
+        // "Central and hot": PageRank times log-scaled changed lines.
+        Metric::Churn => scores
+            .iter()
+            .zip(&churn)
+            .map(|(s, c)| s * c.map_or(0.0, |c| (1.0 + c.lines as f64).ln()))
+            .collect(),
+        // The opposite: heavily depended on and rarely touched.
+        Metric::Foundation => scores
+            .iter()
+            .zip(&churn)
+            .map(|(s, c)| s / (1.0 + c.map_or(0.0, |c| (1.0 + c.lines as f64).ln())))
+            .collect(),
+        _ => scores,
+    };
+
+    let warnings = analyze::warnings(graph, &scores, args.workspace_only);
 
-    let mut filtered: Vec<_> = scores
-        .into_iter()
-        .filter(|(name, _)| !args.workspace_only || workspace_members.contains(name))
+    let mut filtered: Vec<_> = graph
+        .node_indices()
+        .filter(|&i| !args.workspace_only || graph[i].workspace_member)
+        .map(|i| (i, scores[i.index()]))
         .collect();
 
-    filtered.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    filtered.sort_by(|a, b| {
+        centrality::by_score_desc(a.1, b.1)
+            .then_with(|| graph[a.0].name.cmp(&graph[b.0].name))
+            .then_with(|| graph[a.0].version.cmp(&graph[b.0].version))
+    });
+
+    let rows = filtered
+        .iter()
+        .take(args.top)
+        .enumerate()
+        .map(|(rank, &(i, score))| AnalyzeRow {
+            rank: rank + 1,
+            name: graph[i].name.clone(),
+            version: graph[i].version.clone(),
+            score,
+            workspace_member: graph[i].workspace_member,
+            owner: graph[i].owner.clone(),
+            workspace: graph[i].workspace.clone(),
+            churn: churn[i.index()],
+            depths: (args.depths && graph[i].workspace_member)
+                .then(|| analyze::depth_histogram(graph, i)),
+        })
+        .collect();
 
-    println!("Top {} by {:?}:", args.top, args.metric);
-    println!("{:─<50}", "");
-    for (i, (name, score)) in filtered.iter().take(args.top).enumerate() {
-        println!("{:3}. {:40} {:.6}", i + 1, name, score);
+    Ok(AnalyzeOut {
+        metric: args.metric,
+        focus: args.focus.as_ref().map(|spec| analyze::Focus {
+            package: spec.clone(),
+            radius: args.radius,
+        }),
+        group_by: args.group_by,
+        pagerank_config: convergence.map(|_| config),
+        convergence,
+        nodes: graph.node_count(),
+        edges: graph.edge_count(),
+        rows,
+        diagnostics: analyze::diagnostics(metadata, graph, args.workspace_only, args.graph.nested),
+        warnings,
+        target_exclusive: targets::exclusive(graph, &args.graph.targets()),
+        licenses: args
+            .licenses
+            .then(|| license::license_report(graph, &config)),
+    })
+}
+
+fn run_why(args: &WhyArgs) -> anyhow::Result<()> {
+    let (_, graph) = args.graph.load()?;
+
+    let out = why::why(
+        &graph,
+        &args.from,
+        &args.to,
+        args.max_paths,
+        &args.pagerank.config()?,
+    )?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        why::print_text(&out);
     }
-    println!("\n{} nodes, {} edges", graph.node_count(), graph.edge_count());
 
     Ok(())
 }
 
-fn pagerank<'a>(graph: &'a DiGraph<&'a str, ()>) -> Vec<(&'a str, f64)> {
-    let n = graph.node_count();
-    if n == 0 { return vec![]; }
+fn run_impact(args: &ImpactArgs) -> anyhow::Result<()> {
+    let (_, graph) = args.graph.load()?;
 
-    let damping = 0.85;
-    let mut scores: Vec<f64> = vec![1.0 / n as f64; n];
-    let mut new_scores = vec![0.0; n];
+    let out = impact::impact(&graph, &args.krate, args.top, &args.pagerank.config()?)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        impact::print_text(&out);
+    }
 
-    for _ in 0..100 {
-        let mut diff = 0.0;
-        for node in graph.node_indices() {
-            let mut sum = 0.0;
-            for neighbor in graph.neighbors_directed(node, Direction::Incoming) {
-                let out_deg = graph.neighbors_directed(neighbor, Direction::Outgoing).count() as f64;
-                if out_deg > 0.0 { sum += scores[neighbor.index()] / out_deg; }
-            }
-            new_scores[node.index()] = (1.0 - damping) / n as f64 + damping * sum;
-            diff += (new_scores[node.index()] - scores[node.index()]).abs();
-        }
-        std::mem::swap(&mut scores, &mut new_scores);
-        if diff < 1e-8 { break; }
+    Ok(())
+}
+
+fn run_orphans(args: &OrphansArgs) -> anyhow::Result<()> {
+    let (metadata, graph) = args.graph.load()?;
+
+    let rows = orphans::orphans(
+        &graph,
+        metadata.workspace_root.as_std_path(),
+        args.stale_days,
+    );
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    } else {
+        orphans::print_text(&rows);
+    }
+
+    Ok(())
+}
+
+fn run_gateways(args: &GatewaysArgs) -> anyhow::Result<()> {
+    let (_, graph) = args.graph.load()?;
+
+    let rows = gateways::gateways(&graph, args.min_gated, &args.pagerank.config()?);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    } else {
+        gateways::print_text(&rows, args.top);
     }
 
-    graph.node_indices().map(|i| (*graph.node_weight(i).unwrap(), scores[i.index()])).collect()
+    Ok(())
 }
 
-fn degree_centrality<'a>(graph: &'a DiGraph<&'a str, ()>, dir: Direction) -> Vec<(&'a str, f64)> {
-    let n = graph.node_count() as f64;
-    if n <= 1.0 {
-        return graph.node_indices().map(|i| (*graph.node_weight(i).unwrap(), 0.0)).collect();
+fn run_features_impact(args: &FeaturesImpactArgs) -> anyhow::Result<()> {
+    let g = &args.graph;
+    if !g.targets.is_empty() || g.nested || !g.merge.is_empty() || g.merge_list.is_some() {
+        bail!(
+            "features-impact analyzes a single workspace and target; drop --targets, --nested, --merge and --merge-list"
+        );
     }
-    graph.node_indices().map(|i| {
-        let deg = graph.neighbors_directed(i, dir).count() as f64 / (n - 1.0);
-        (*graph.node_weight(i).unwrap(), deg)
-    }).collect()
+    let (features, opt) = if args.all_features {
+        (vec!["all features".to_string()], CargoOpt::AllFeatures)
+    } else {
+        (
+            args.features.clone(),
+            CargoOpt::SomeFeatures(args.features.clone()),
+        )
+    };
+
+    let platform = args.graph.filter_platform.as_deref();
+    let baseline = args
+        .graph
+        .graph(&load_metadata(&args.graph.path, platform, None)?)?;
+    let with_features = args
+        .graph
+        .graph(&load_metadata(&args.graph.path, platform, Some(opt))?)?;
+
+    let out = features::features_impact(
+        &baseline,
+        &with_features,
+        features,
+        args.top,
+        &args.pagerank.config()?,
+    );
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        features::print_text(&out);
+    }
+
+    Ok(())
 }
 
-fn betweenness_centrality<'a>(graph: &'a DiGraph<&'a str, ()>) -> Vec<(&'a str, f64)> {
-    let n = graph.node_count();
-    if n <= 2 {
-        return graph.node_indices().map(|i| (*graph.node_weight(i).unwrap(), 0.0)).collect();
+fn run_dupes(args: &DupesArgs) -> anyhow::Result<()> {
+    let (_, graph) = args.graph.load()?;
+
+    let dupes = dupes::dupes(&graph);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&dupes)?);
+    } else {
+        dupes::print_text(&dupes);
     }
 
-    let mut betweenness = vec![0.0; n];
+    Ok(())
+}
 
-    for s in graph.node_indices() {
-        let mut stack = Vec::new();
-        let mut pred: Vec<Vec<NodeIndex>> = vec![vec![]; n];
-        let mut sigma = vec![0.0; n];
-        let mut dist: Vec<i32> = vec![-1; n];
+fn run_digest(args: &DigestArgs) -> anyhow::Result<()> {
+    let (metadata, graph) = args.graph.load()?;
 
-        sigma[s.index()] = 1.0;
-        dist[s.index()] = 0;
+    let state_path = args.state.clone().unwrap_or_else(|| {
+        artifacts::DIGEST_STATE.path(
+            metadata
+                .workspace_root
+                .join(artifacts::DEFAULT_DIR)
+                .as_std_path(),
+        )
+    });
+    let state = digest::compute(&graph, &args.pagerank.config()?);
+    let previous = if args.since_last {
+        digest::load_state(&state_path)?
+    } else {
+        None
+    };
+    let delta = previous.as_ref().map(|prev| digest::diff(prev, &state));
 
-        let mut queue = std::collections::VecDeque::new();
-        queue.push_back(s);
+    let report = match args.format {
+        DigestFormat::Text => digest::render_text(&state, delta.as_ref(), args.top),
+        DigestFormat::Markdown => digest::render_markdown(&state, delta.as_ref(), args.top),
+    };
+    print!("{}", report);
 
-        while let Some(v) = queue.pop_front() {
-            stack.push(v);
-            for w in graph.neighbors_directed(v, Direction::Outgoing) {
-                if dist[w.index()] < 0 {
-                    dist[w.index()] = dist[v.index()] + 1;
-                    queue.push_back(w);
-                }
-                if dist[w.index()] == dist[v.index()] + 1 {
-                    sigma[w.index()] += sigma[v.index()];
-                    pred[w.index()].push(v);
-                }
-            }
-        }
+    if !args.dry_run {
+        digest::save_state(&state_path, metadata.workspace_root.as_std_path(), &state)?;
+    }
 
-        let mut delta = vec![0.0; n];
-        while let Some(w) = stack.pop() {
-            for &v in &pred[w.index()] {
-                delta[v.index()] += (sigma[v.index()] / sigma[w.index()]) * (1.0 + delta[w.index()]);
-            }
-            if w != s { betweenness[w.index()] += delta[w.index()]; }
-        }
+    Ok(())
+}
+
+fn run_sbom(args: &SbomArgs) -> anyhow::Result<()> {
+    let (metadata, graph) = args.graph.load()?;
+
+    let name = match metadata.root_package() {
+        Some(pkg) => pkg.name.to_string(),
+        None => metadata
+            .workspace_root
+            .file_name()
+            .unwrap_or("workspace")
+            .to_string(),
+    };
+    let doc = sbom::sbom(
+        &graph,
+        &name,
+        args.format,
+        args.caps.caps(),
+        &args.pagerank.config()?,
+    );
+    println!("{}", serde_json::to_string_pretty(&doc)?);
+
+    Ok(())
+}
+
+fn run_owners(args: &OwnersArgs) -> anyhow::Result<()> {
+    let (metadata, graph) = args.graph.load()?;
+
+    let rows = owners::rollup(&graph, &args.pagerank.config()?);
+    if let Some(dir) = &args.out {
+        artifacts::OWNERS.write(dir, Some(metadata.workspace_root.as_std_path()), &rows)?;
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    } else {
+        owners::print_text(&rows);
+    }
+
+    Ok(())
+}
+
+fn run_modules(args: &ModulesArgs) -> anyhow::Result<()> {
+    let started = Instant::now();
+    let metadata = load_metadata(&args.path, None, None)?;
+    let metadata_time = started.elapsed();
+
+    let out = modules::modules(
+        &metadata,
+        args.package.as_deref(),
+        args.bin.as_deref(),
+        modules::ModulesOptions {
+            backend: args.backend,
+            top: args.top,
+            per_kloc: args.per_kloc,
+            report_cycles: args.report_cycles,
+            api_surface: args.api_surface,
+            report_unused: args.report_unused,
+            workspace: args.workspace,
+            emit_graph: args.emit_graph.is_some(),
+            caps: args.caps.caps(),
+            pagerank: args.pagerank.config()?,
+        },
+    )?;
+    for warning in &out.warnings {
+        eprintln!("warning: {}", warning);
+    }
+    if args.stats {
+        let phases: Vec<String> = std::iter::once(("metadata", metadata_time))
+            .chain(out.timings.iter().copied())
+            .map(|(phase, time)| format!("{} {}ms", phase, time.as_millis()))
+            .collect();
+        eprintln!(
+            "stats: {}; {} modules, {} edges",
+            phases.join(", "),
+            out.nodes,
+            out.edges
+        );
+    }
+    if let (Some(path), Some(graph)) = (&args.emit_graph, &out.graph) {
+        modules::write_graph(path, graph)?;
+    }
+    if let Some(dir) = &args.out {
+        artifacts::MODULES.write(dir, Some(metadata.workspace_root.as_std_path()), &out)?;
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        modules::print_text(&out);
+    }
+
+    Ok(())
+}
+
+fn run_ownership(args: &OwnershipArgs) -> anyhow::Result<()> {
+    let (_, graph) = args.graph.load()?;
+
+    let rows = ownership::ownership(&graph, args.days, &args.pagerank.config()?);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    } else {
+        ownership::print_text(&rows);
+    }
+
+    Ok(())
+}
+
+fn run_co_change(args: &CoChangeArgs) -> anyhow::Result<()> {
+    let (metadata, graph) = args.graph.load()?;
+
+    let out = cochange::co_change(
+        &graph,
+        metadata.workspace_root.as_std_path(),
+        args.days,
+        args.min_commits,
+        args.max_files,
+    );
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        cochange::print_text(&out, args.top);
+    }
+
+    Ok(())
+}
+
+fn run_watch(args: &WatchArgs) -> anyhow::Result<()> {
+    let a = &args.analyze;
+    if a.json || a.stream || a.out.is_some() {
+        bail!("watch prints text deltas only; drop --json, --stream and --out");
     }
+    let (metadata, graph) = args.analyze.graph.load()?;
+    let out = analyze_graph(&args.analyze, &metadata, &graph, &mut |_| {})?;
+    analyze::print_text(&out, args.analyze.top);
 
-    let norm = if n > 2 { 2.0 / ((n - 1) * (n - 2)) as f64 } else { 1.0 };
-    graph.node_indices().map(|i| (*graph.node_weight(i).unwrap(), betweenness[i.index()] * norm)).collect()
+    watch::watch(
+        metadata.workspace_root.as_std_path(),
+        Duration::from_millis(args.debounce_ms),
+        out,
+        || {
+            let (metadata, graph) = args.analyze.graph.load()?;
+            analyze_graph(&args.analyze, &metadata, &graph, &mut |_| {})
+        },
+    )
+}
+
+fn run_gen_fixture(args: &GenFixtureArgs) -> anyhow::Result<()> {
+    let fixture = fixture::plan(args.crates, args.edges, args.layout, args.seed);
+    fixture::write(&args.dir, &fixture)?;
+    println!(
+        "wrote {} crates and {} edges to {}",
+        fixture.crates.len(),
+        fixture.edges.len(),
+        args.dir.display()
+    );
+    Ok(())
+}
+
+/// `dir`, or `.pkgrank` under the current workspace's root (where digest
+/// writes), or `.pkgrank` here outside a workspace.
+fn artifact_dir(dir: &Option<PathBuf>) -> PathBuf {
+    dir.clone().unwrap_or_else(|| {
+        graph::current_workspace_root()
+            .unwrap_or_default()
+            .join(artifacts::DEFAULT_DIR)
+    })
+}
+
+fn run_snapshot(args: &SnapshotArgs) -> anyhow::Result<()> {
+    let (target, manifest) = snapshot::snapshot(&artifact_dir(&args.out), args.label.as_deref())?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&manifest)?);
+    } else {
+        snapshot::print_text(&target, &manifest);
+    }
+    Ok(())
+}
+
+fn run_compare_runs(args: &CompareRunsArgs) -> anyhow::Result<()> {
+    let out = compare::compare(&args.old, &args.new, args.min_move)?;
+    match args.format {
+        CompareFormat::Text => print!("{}", compare::render_text(&out)),
+        CompareFormat::Json => println!("{}", serde_json::to_string_pretty(&out)?),
+        CompareFormat::Markdown => print!("{}", compare::render_markdown(&out)),
+    }
+    Ok(())
+}
+
+fn run_gc(args: &GcArgs) -> anyhow::Result<()> {
+    let retention = gc::Retention {
+        max_age_days: args.max_age_days,
+        max_bytes: args.max_bytes,
+    };
+    let out = gc::gc(&artifact_dir(&args.out), retention, args.dry_run)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        gc::print_text(&out);
+    }
+    Ok(())
+}
+
+fn run_artifacts_list(args: &ArtifactsListArgs) -> anyhow::Result<()> {
+    let rows = artifacts::list(args.dir.as_deref());
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    } else {
+        artifacts::print_text(&rows, args.dir.as_deref());
+    }
+    Ok(())
+}
+
+fn run_selfcheck(args: &SelfcheckArgs) -> anyhow::Result<()> {
+    let dir = selfcheck::manifest_dir();
+    let result = selfcheck::run(&dir)?;
+
+    if args.bless {
+        let path = selfcheck::bless(&dir, &result)?;
+        println!("wrote {}", path.display());
+        return Ok(());
+    }
+
+    let diffs = selfcheck::compare(&result)?;
+    if diffs.is_empty() {
+        println!(
+            "selfcheck ok: {} nodes, {} edges, top {} match",
+            result.nodes,
+            result.edges,
+            result.top.len()
+        );
+        return Ok(());
+    }
+    for diff in &diffs {
+        eprintln!("{}", diff);
+    }
+    bail!(
+        "selfcheck failed: {} differences from the golden result",
+        diffs.len()
+    )
+}
+
+fn run_schema(args: &SchemaArgs) -> anyhow::Result<()> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&schema::schema(args.payload))?
+    );
+    Ok(())
 }
@@ -0,0 +1,1054 @@
+//! `pkgrank modules`: rank a crate's modules by how the rest of the crate
+//! uses them.
+//!
+//! The `syn` backend parses the target's sources directly: `mod` items give
+//! the module tree, and `use` items plus `crate::`/`self::`/`super::` (or
+//! child-module) paths give the edges. Paths inside macro calls are only
+//! seen when the macro body parses as comma-separated expressions, and
+//! re-exports are credited to the module that re-exports, so the graph is an
+//! approximation of what the compiler resolves.
+
+use crate::centrality::{self, PageRankConfig, Weighted};
+use crate::truncate::{self, Caps};
+use anyhow::{Context, bail};
+use cargo_metadata::{Metadata, Package, Target, TargetKind};
+use clap::ValueEnum;
+use petgraph::prelude::*;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, Item, ItemMod, ItemUse, Token, UseTree, Visibility};
+
+#[derive(Debug, Clone, Copy, ValueEnum, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// Parse `mod`/`use` items with syn; no extra tools needed
+    Syn,
+}
+
+#[derive(Debug, Clone)]
+pub struct Module {
+    /// `crate::a::b`
+    pub path: String,
+    /// Source file declaring the module's items.
+    pub file: PathBuf,
+    /// Lines and bytes spanned by the module's own items (not its inline
+    /// submodules).
+    pub loc: usize,
+    pub bytes: usize,
+    /// Items declared with some `pub` visibility.
+    pub items: Vec<Declared>,
+    /// Names of those items referenced from within the module itself.
+    pub used_locally: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Declared {
+    pub name: String,
+    /// Plain `pub`, as opposed to `pub(crate)` and friends.
+    pub public: bool,
+}
+
+/// References from one module to another.
+#[derive(Debug, Clone, Default)]
+pub struct Uses {
+    pub refs: usize,
+    /// Distinct referenced paths, e.g. `crate::graph::DepGraph`.
+    pub items: BTreeSet<String>,
+}
+
+impl Weighted for Uses {
+    fn weight(&self) -> f64 {
+        self.refs as f64
+    }
+}
+
+pub type ModuleGraph = DiGraph<Module, Uses>;
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ModuleRow {
+    pub rank: usize,
+    pub module: String,
+    /// Relative to the package directory.
+    pub file: String,
+    pub pagerank: f64,
+    /// Pagerank per 1000 lines (with `--per-kloc`, the ranking key).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagerank_per_kloc: Option<f64>,
+    pub betweenness: f64,
+    pub loc: usize,
+    pub bytes: usize,
+    /// Modules that use this one.
+    pub dependents: usize,
+    /// Modules this one uses.
+    pub dependencies: usize,
+    /// Distinct items other modules reference (with `--api-surface`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_surface: Option<usize>,
+    /// Those items, by the summed pagerank of the modules using them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub api_items: Vec<ApiItem>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ApiItem {
+    /// Item name within the module (first path segment after it).
+    pub name: String,
+    /// Modules referencing it.
+    pub users: usize,
+    /// Sum of those modules' pagerank.
+    pub user_pagerank: f64,
+}
+
+/// What `modules` computes beyond the basic ranking.
+#[derive(Debug, Clone, Copy)]
+pub struct ModulesOptions {
+    pub backend: Backend,
+    /// Rows to keep.
+    pub top: usize,
+    /// Rank by pagerank per 1000 lines.
+    pub per_kloc: bool,
+    pub report_cycles: bool,
+    pub api_surface: bool,
+    pub report_unused: bool,
+    /// Merge every workspace member into one graph.
+    pub workspace: bool,
+    /// Fill [`ModulesOut::graph`].
+    pub emit_graph: bool,
+    /// Limits on the exported graph (scores use the whole graph).
+    pub caps: Caps,
+    pub pagerank: PageRankConfig,
+}
+
+/// The scored graph itself, for `--emit-graph`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GraphExport {
+    pub nodes: Vec<ExportNode>,
+    pub edges: Vec<ExportEdge>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ExportNode {
+    /// Index referenced by [`ExportEdge`].
+    pub id: usize,
+    pub module: String,
+    pub file: String,
+    pub loc: usize,
+    pub pagerank: f64,
+    pub betweenness: f64,
+}
+
+/// `from` uses `to`, weighted by reference count.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ExportEdge {
+    pub from: usize,
+    pub to: usize,
+    pub refs: usize,
+    pub items: Vec<String>,
+}
+
+/// Write `graph` as DOT if `path` ends in `.dot`, else as JSON.
+pub fn write_graph(path: &Path, graph: &GraphExport) -> anyhow::Result<()> {
+    if path.extension().is_none_or(|e| e != "dot") {
+        return crate::artifacts::write_json(path, graph);
+    }
+    let mut dot = String::from("digraph modules {\n");
+    for node in &graph.nodes {
+        dot.push_str(&format!(
+            "  n{} [label={:?}, file={:?}, loc={}, pagerank={}];\n",
+            node.id, node.module, node.file, node.loc, node.pagerank
+        ));
+    }
+    for edge in &graph.edges {
+        dot.push_str(&format!(
+            "  n{} -> n{} [weight={}];\n",
+            edge.from, edge.to, edge.refs
+        ));
+    }
+    dot.push_str("}\n");
+    std::fs::write(path, dot).with_context(|| format!("writing {}", path.display()))
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ModulesOut {
+    pub package: String,
+    pub version: String,
+    pub target: String,
+    pub backend: Backend,
+    pub nodes: usize,
+    pub edges: usize,
+    pub rows: Vec<ModuleRow>,
+    /// Crates merged into the graph (with `--workspace`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub crates: Vec<String>,
+    /// Strongly connected groups of modules (with `--report-cycles`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cycles: Vec<ModuleCycle>,
+    /// Wall time per phase, for `--stats`.
+    #[serde(skip)]
+    pub timings: Vec<(&'static str, Duration)>,
+    /// Every node and edge (with `--emit-graph`; written to its own file).
+    #[serde(skip)]
+    pub graph: Option<GraphExport>,
+    /// Cleanup candidates (with `--report-unused`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unused: Option<Unused>,
+    /// `mod` declarations whose file could not be found or parsed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Items of `module` referenced from other modules. A reference to the
+/// module itself (`use crate::graph;`) names no item and is skipped.
+fn api_items(graph: &ModuleGraph, module: NodeIndex, pagerank: &[f64]) -> Vec<ApiItem> {
+    let prefix = format!("{}::", graph[module].path);
+    let mut users: BTreeMap<&str, BTreeSet<NodeIndex>> = BTreeMap::new();
+    for edge in graph.edges_directed(module, Direction::Incoming) {
+        for item in &edge.weight().items {
+            if let Some(rest) = item.strip_prefix(&prefix) {
+                let name = rest.split("::").next().unwrap_or(rest);
+                users.entry(name).or_default().insert(edge.source());
+            }
+        }
+    }
+    let mut items: Vec<ApiItem> = users
+        .into_iter()
+        .map(|(name, users)| ApiItem {
+            name: name.to_string(),
+            users: users.len(),
+            user_pagerank: users.iter().map(|u| pagerank[u.index()]).sum(),
+        })
+        .collect();
+    items.sort_by(|a, b| {
+        centrality::by_score_desc(a.user_pagerank, b.user_pagerank)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    items
+}
+
+/// Code nothing else in the target refers to.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Unused {
+    /// Modules no other module uses (the crate root aside). In a library,
+    /// a `pub mod` may still be used by other crates.
+    pub modules: Vec<String>,
+    /// `module::item` for visible items nothing references, in their own
+    /// module or elsewhere. Plain
+    /// `pub` items only count in binaries, where nothing outside can use them.
+    pub items: Vec<String>,
+    /// `.rs` files under the target's directory that no `mod` reaches,
+    /// relative to the package directory.
+    pub orphan_files: Vec<String>,
+}
+
+fn unused(graph: &ModuleGraph, pagerank: &[f64], bin: bool, orphan_files: Vec<String>) -> Unused {
+    let mut modules = Vec::new();
+    let mut items = Vec::new();
+    for i in graph.node_indices() {
+        let module = &graph[i];
+        // Crate roots are used from outside by definition.
+        if !module.path.contains("::") {
+            continue;
+        }
+        if graph
+            .neighbors_directed(i, Direction::Incoming)
+            .next()
+            .is_none()
+        {
+            modules.push(module.path.clone());
+        }
+        let used: BTreeSet<String> = api_items(graph, i, pagerank)
+            .into_iter()
+            .map(|item| item.name)
+            .collect();
+        items.extend(
+            module
+                .items
+                .iter()
+                .filter(|d| {
+                    (bin || !d.public)
+                        && !used.contains(&d.name)
+                        && !module.used_locally.contains(&d.name)
+                })
+                .map(|d| format!("{}::{}", module.path, d.name)),
+        );
+    }
+    modules.sort();
+    items.sort();
+    Unused {
+        modules,
+        items,
+        orphan_files,
+    }
+}
+
+/// `.rs` files under the target root's directory that are not in `parsed`.
+/// Other targets' roots are skipped, and so is everything under a
+/// directory holding one (`src/bin/tool/main.rs`).
+fn orphan_files(package: &Package, target: &Target, parsed: &BTreeSet<&Path>) -> Vec<PathBuf> {
+    let Some(dir) = target.src_path.parent() else {
+        return vec![];
+    };
+    let others: Vec<&Path> = package
+        .targets
+        .iter()
+        .filter(|t| t.src_path != target.src_path)
+        .map(|t| t.src_path.as_std_path())
+        .collect();
+    let mut found = Vec::new();
+    let mut stack = vec![dir.as_std_path().to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                if !others.iter().any(|o| o.parent() == Some(path.as_path())) {
+                    stack.push(path);
+                }
+            } else if path.extension().is_some_and(|e| e == "rs")
+                && !parsed.contains(path.as_path())
+                && !others.contains(&path.as_path())
+            {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// Modules that all reach each other through `use` edges.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ModuleCycle {
+    /// Sorted module paths.
+    pub modules: Vec<String>,
+    /// Edges between members of the group.
+    pub edges: Vec<CycleEdge>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CycleEdge {
+    pub from: String,
+    pub to: String,
+    /// Referenced items, e.g. `crate::graph::DepGraph`.
+    pub items: Vec<String>,
+}
+
+/// Non-trivial strongly connected components, largest first.
+pub fn cycles(graph: &ModuleGraph) -> Vec<ModuleCycle> {
+    let mut cycles: Vec<ModuleCycle> = petgraph::algo::tarjan_scc(graph)
+        .into_iter()
+        .filter(|scc| scc.len() > 1)
+        .map(|scc| {
+            let members: BTreeSet<NodeIndex> = scc.iter().copied().collect();
+            let mut modules: Vec<String> = scc.iter().map(|&i| graph[i].path.clone()).collect();
+            modules.sort();
+            let mut edges: Vec<CycleEdge> = graph
+                .edge_references()
+                .filter(|e| members.contains(&e.source()) && members.contains(&e.target()))
+                .map(|e| CycleEdge {
+                    from: graph[e.source()].path.clone(),
+                    to: graph[e.target()].path.clone(),
+                    items: e.weight().items.iter().cloned().collect(),
+                })
+                .collect();
+            edges.sort_by(|a, b| a.from.cmp(&b.from).then_with(|| a.to.cmp(&b.to)));
+            ModuleCycle { modules, edges }
+        })
+        .collect();
+    cycles.sort_by(|a, b| {
+        b.modules
+            .len()
+            .cmp(&a.modules.len())
+            .then_with(|| a.modules.cmp(&b.modules))
+    });
+    cycles
+}
+
+/// The workspace package to analyze: `name` if given, else the root package,
+/// else the only workspace member.
+pub fn select_package<'a>(
+    metadata: &'a Metadata,
+    name: Option<&str>,
+) -> anyhow::Result<&'a Package> {
+    let members = metadata.workspace_packages();
+    if let Some(name) = name {
+        return members
+            .into_iter()
+            .find(|p| p.name.as_str() == name)
+            .with_context(|| format!("`{}` is not a workspace member", name));
+    }
+    if let Some(root) = metadata.root_package() {
+        return Ok(root);
+    }
+    match members.as_slice() {
+        [only] => Ok(only),
+        _ => {
+            let names: Vec<&str> = members.iter().map(|p| p.name.as_str()).collect();
+            bail!("choose a package with --package ({})", names.join(", "))
+        }
+    }
+}
+
+/// The library target, or the binary named `bin` (or the first binary when
+/// there is no library).
+pub fn select_target<'a>(package: &'a Package, bin: Option<&str>) -> anyhow::Result<&'a Target> {
+    let is_lib = |t: &&Target| {
+        t.kind.iter().any(|k| {
+            matches!(
+                k,
+                TargetKind::Lib
+                    | TargetKind::RLib
+                    | TargetKind::DyLib
+                    | TargetKind::CDyLib
+                    | TargetKind::StaticLib
+                    | TargetKind::ProcMacro
+            )
+        })
+    };
+    let target = match bin {
+        Some(name) => package
+            .targets
+            .iter()
+            .find(|t| t.is_bin() && t.name == name),
+        None => package
+            .targets
+            .iter()
+            .find(is_lib)
+            .or_else(|| package.targets.iter().find(|t| t.is_bin())),
+    };
+    target.with_context(|| match bin {
+        Some(name) => format!("{} has no binary target `{}`", package.name, name),
+        None => format!("{} has no lib or bin target", package.name),
+    })
+}
+
+struct Parsed {
+    path: Vec<String>,
+    file: PathBuf,
+    items: Vec<Item>,
+}
+
+/// Build the module graph of the crates given as (name, root file), e.g.
+/// `("crate", "src/lib.rs")`. Module paths start with the crate name; with
+/// several crates, a path starting with another crate's name resolves into
+/// that crate.
+pub fn module_graph(crates: &[(String, &Path)]) -> anyhow::Result<(ModuleGraph, Vec<String>)> {
+    let mut parsed = Vec::new();
+    let mut warnings = Vec::new();
+    for (name, root) in crates {
+        let source = std::fs::read_to_string(root)
+            .with_context(|| format!("failed to read {}", root.display()))?;
+        let file = syn::parse_file(&source)
+            .with_context(|| format!("failed to parse {}", root.display()))?;
+        let dir = root.parent().unwrap_or(Path::new("."));
+        collect(
+            &mut parsed,
+            &mut warnings,
+            vec![name.clone()],
+            root,
+            dir,
+            file.items,
+        );
+    }
+
+    let mut graph = ModuleGraph::new();
+    let mut index: HashMap<Vec<String>, NodeIndex> = HashMap::new();
+    for m in &parsed {
+        let (loc, bytes) = size(&m.items);
+        let idx = graph.add_node(Module {
+            path: m.path.join("::"),
+            file: m.file.clone(),
+            loc,
+            bytes,
+            items: declared(&m.items),
+            used_locally: BTreeSet::new(),
+        });
+        index.insert(m.path.clone(), idx);
+    }
+
+    for m in &parsed {
+        let from = index[&m.path];
+        let mut refs = References {
+            module: &m.path,
+            modules: &index,
+            local: graph[from].items.iter().map(|d| d.name.clone()).collect(),
+            aliases: HashMap::new(),
+            found: Vec::new(),
+        };
+        let mut bindings = Vec::new();
+        for item in &m.items {
+            if let Item::Use(u) = item
+                && u.leading_colon.is_none()
+            {
+                use_bindings(&u.tree, &mut Vec::new(), &mut bindings);
+            }
+        }
+        for (name, path) in bindings {
+            if let Some(absolute) = refs.resolve(path) {
+                refs.aliases.insert(name, absolute);
+            }
+        }
+        for item in &m.items {
+            refs.visit_item(item);
+        }
+        for (target, item) in refs.found {
+            let to = index[&target];
+            if to == from {
+                let prefix = format!("{}::", graph[from].path);
+                if let Some(rest) = item.strip_prefix(&prefix) {
+                    let name = rest.split("::").next().unwrap_or(rest).to_string();
+                    graph[from].used_locally.insert(name);
+                }
+                continue;
+            }
+            let edge = match graph.find_edge(from, to) {
+                Some(e) => e,
+                None => graph.add_edge(from, to, Uses::default()),
+            };
+            graph[edge].refs += 1;
+            graph[edge].items.insert(item);
+        }
+    }
+
+    Ok((graph, warnings))
+}
+
+fn collect(
+    out: &mut Vec<Parsed>,
+    warnings: &mut Vec<String>,
+    path: Vec<String>,
+    file: &Path,
+    children_dir: &Path,
+    items: Vec<Item>,
+) {
+    for item in &items {
+        let Item::Mod(m) = item else {
+            continue;
+        };
+        if is_cfg_test(m) {
+            continue;
+        }
+        let name = m.ident.to_string();
+        let mut child_path = path.clone();
+        child_path.push(name.clone());
+
+        if let Some((_, content)) = &m.content {
+            collect(
+                out,
+                warnings,
+                child_path,
+                file,
+                &children_dir.join(&name),
+                content.clone(),
+            );
+            continue;
+        }
+
+        let candidates = match path_attr(m) {
+            Some(p) => vec![file.parent().unwrap_or(Path::new(".")).join(p)],
+            None => vec![
+                children_dir.join(format!("{}.rs", name)),
+                children_dir.join(&name).join("mod.rs"),
+            ],
+        };
+        let Some(child_file) = candidates.into_iter().find(|p| p.is_file()) else {
+            warnings.push(format!("{}: no file for `mod {}`", file.display(), name));
+            continue;
+        };
+        let parsed = std::fs::read_to_string(&child_file)
+            .map_err(anyhow::Error::from)
+            .and_then(|s| Ok(syn::parse_file(&s)?));
+        let child = match parsed {
+            Ok(child) => child,
+            Err(err) => {
+                warnings.push(format!("{}: {}", child_file.display(), err));
+                continue;
+            }
+        };
+        let grandchildren_dir = if child_file.file_name().is_some_and(|f| f == "mod.rs") {
+            child_file.parent().unwrap_or(children_dir).to_path_buf()
+        } else {
+            children_dir.join(&name)
+        };
+        collect(
+            out,
+            warnings,
+            child_path,
+            &child_file,
+            &grandchildren_dir,
+            child.items,
+        );
+    }
+
+    out.push(Parsed {
+        path,
+        file: file.to_path_buf(),
+        items,
+    });
+}
+
+/// Lines and bytes covered by `items`, leaving out inline submodules.
+fn size(items: &[Item]) -> (usize, usize) {
+    items
+        .iter()
+        .filter(|item| !matches!(item, Item::Mod(m) if m.content.is_some()))
+        .map(|item| {
+            let span = item.span();
+            (
+                span.end().line - span.start().line + 1,
+                span.byte_range().len(),
+            )
+        })
+        .fold((0, 0), |(l, b), (dl, db)| (l + dl, b + db))
+}
+
+fn declared(items: &[Item]) -> Vec<Declared> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Const(i) => Some((&i.vis, &i.ident)),
+            Item::Enum(i) => Some((&i.vis, &i.ident)),
+            Item::Fn(i) => Some((&i.vis, &i.sig.ident)),
+            Item::Static(i) => Some((&i.vis, &i.ident)),
+            Item::Struct(i) => Some((&i.vis, &i.ident)),
+            Item::Trait(i) => Some((&i.vis, &i.ident)),
+            Item::Type(i) => Some((&i.vis, &i.ident)),
+            Item::Union(i) => Some((&i.vis, &i.ident)),
+            _ => None,
+        })
+        .filter(|(vis, _)| !matches!(vis, Visibility::Inherited))
+        .map(|(vis, ident)| Declared {
+            name: ident.to_string(),
+            public: matches!(vis, Visibility::Public(_)),
+        })
+        .collect()
+}
+
+fn is_cfg_test(m: &ItemMod) -> bool {
+    m.attrs.iter().any(|a| {
+        a.path().is_ident("cfg") && a.parse_args::<syn::Ident>().is_ok_and(|i| i == "test")
+    })
+}
+
+fn path_attr(m: &ItemMod) -> Option<String> {
+    m.attrs.iter().find_map(|a| match &a.meta {
+        syn::Meta::NameValue(nv) if nv.path.is_ident("path") => match &nv.value {
+            Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(s),
+                ..
+            }) => Some(s.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Collects `(module, referenced path)` for every path in one module's items
+/// that resolves to a module of this crate.
+struct References<'a> {
+    module: &'a [String],
+    modules: &'a HashMap<Vec<String>, NodeIndex>,
+    /// Visible items declared in the module itself.
+    local: HashSet<String>,
+    /// Names brought into scope by the module's `use` items.
+    aliases: HashMap<String, Vec<String>>,
+    found: Vec<(Vec<String>, String)>,
+}
+
+impl References<'_> {
+    fn record(&mut self, segments: Vec<String>) {
+        let Some(absolute) = self.resolve(segments) else {
+            return;
+        };
+        let target = (1..=absolute.len())
+            .rev()
+            .map(|n| &absolute[..n])
+            .find(|prefix| self.modules.contains_key(*prefix));
+        if let Some(target) = target {
+            self.found.push((target.to_vec(), absolute.join("::")));
+        }
+    }
+
+    fn resolve(&self, segments: Vec<String>) -> Option<Vec<String>> {
+        let first = segments.first()?;
+        match first.as_str() {
+            "crate" => Some(
+                self.module[..1]
+                    .iter()
+                    .cloned()
+                    .chain(segments.into_iter().skip(1))
+                    .collect(),
+            ),
+            "self" => Some(
+                self.module
+                    .iter()
+                    .cloned()
+                    .chain(segments.into_iter().skip(1))
+                    .collect(),
+            ),
+            "super" => {
+                let mut base = self.module.to_vec();
+                let mut rest = segments.into_iter().peekable();
+                while rest.next_if(|s| s == "super").is_some() {
+                    if base.len() > 1 {
+                        base.pop();
+                    }
+                }
+                Some(base.into_iter().chain(rest).collect())
+            }
+            _ if self.aliases.contains_key(first) => Some(
+                self.aliases[first]
+                    .iter()
+                    .cloned()
+                    .chain(segments.into_iter().skip(1))
+                    .collect(),
+            ),
+            _ => {
+                let mut child = self.module.to_vec();
+                child.push(first.clone());
+                if self.modules.contains_key(&child) || self.local.contains(first) {
+                    Some(self.module.iter().cloned().chain(segments).collect())
+                } else {
+                    // Another crate of a merged graph.
+                    self.modules
+                        .contains_key(std::slice::from_ref(first))
+                        .then_some(segments)
+                }
+            }
+        }
+    }
+}
+
+/// The names a `use` tree binds, with the path each one stands for.
+fn use_bindings(tree: &UseTree, prefix: &mut Vec<String>, out: &mut Vec<(String, Vec<String>)>) {
+    match tree {
+        UseTree::Path(p) => {
+            prefix.push(p.ident.to_string());
+            use_bindings(&p.tree, prefix, out);
+            prefix.pop();
+        }
+        UseTree::Name(n) if n.ident == "self" => {
+            if let Some(last) = prefix.last() {
+                out.push((last.clone(), prefix.clone()));
+            }
+        }
+        UseTree::Name(n) => {
+            let mut path = prefix.clone();
+            path.push(n.ident.to_string());
+            out.push((n.ident.to_string(), path));
+        }
+        UseTree::Rename(r) => {
+            let mut path = prefix.clone();
+            path.push(r.ident.to_string());
+            out.push((r.rename.to_string(), path));
+        }
+        UseTree::Glob(_) => {}
+        UseTree::Group(g) => {
+            for tree in &g.items {
+                use_bindings(tree, prefix, out);
+            }
+        }
+    }
+}
+
+fn use_paths(tree: &UseTree, prefix: &mut Vec<String>, out: &mut Vec<Vec<String>>) {
+    match tree {
+        UseTree::Path(p) => {
+            prefix.push(p.ident.to_string());
+            use_paths(&p.tree, prefix, out);
+            prefix.pop();
+        }
+        UseTree::Name(n) if n.ident == "self" => out.push(prefix.clone()),
+        UseTree::Name(n) => out.push(
+            prefix
+                .iter()
+                .cloned()
+                .chain([n.ident.to_string()])
+                .collect(),
+        ),
+        UseTree::Rename(r) => out.push(
+            prefix
+                .iter()
+                .cloned()
+                .chain([r.ident.to_string()])
+                .collect(),
+        ),
+        UseTree::Glob(_) => out.push(prefix.clone()),
+        UseTree::Group(g) => {
+            for tree in &g.items {
+                use_paths(tree, prefix, out);
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for References<'_> {
+    // Nested modules are nodes of their own.
+    fn visit_item_mod(&mut self, _: &'ast ItemMod) {}
+
+    fn visit_item_use(&mut self, item: &'ast ItemUse) {
+        if item.leading_colon.is_some() {
+            return;
+        }
+        let mut paths = Vec::new();
+        use_paths(&item.tree, &mut Vec::new(), &mut paths);
+        for path in paths {
+            self.record(path);
+        }
+    }
+
+    fn visit_path(&mut self, path: &'ast syn::Path) {
+        if path.leading_colon.is_none() {
+            self.record(path.segments.iter().map(|s| s.ident.to_string()).collect());
+        }
+        visit::visit_path(self, path);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        if let Ok(args) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for expr in &args {
+                self.visit_expr(expr);
+            }
+        }
+        visit::visit_macro(self, mac);
+    }
+}
+
+pub fn modules(
+    metadata: &Metadata,
+    package: Option<&str>,
+    bin: Option<&str>,
+    opts: ModulesOptions,
+) -> anyhow::Result<ModulesOut> {
+    let selected: Vec<(&Package, &Target)> = if opts.workspace {
+        let mut members = metadata.workspace_packages();
+        members.sort_by(|a, b| a.name.cmp(&b.name));
+        members
+            .into_iter()
+            .map(|p| Ok((p, select_target(p, None)?)))
+            .collect::<anyhow::Result<_>>()?
+    } else {
+        let package = select_package(metadata, package)?;
+        vec![(package, select_target(package, bin)?)]
+    };
+    let roots: Vec<(String, &Path)> = selected
+        .iter()
+        .map(|(_, t)| {
+            let name = if opts.workspace {
+                t.name.replace('-', "_")
+            } else {
+                "crate".to_string()
+            };
+            (name, t.src_path.as_std_path())
+        })
+        .collect();
+    let mut timings = Vec::new();
+    let mut lap = Instant::now();
+    let (graph, warnings) = match opts.backend {
+        Backend::Syn => module_graph(&roots)?,
+    };
+    timings.push(("parse", lap.elapsed()));
+    lap = Instant::now();
+    let (package, target) = selected[0];
+    let base_dir = if opts.workspace {
+        Some(metadata.workspace_root.as_std_path())
+    } else {
+        package.manifest_path.parent().map(|p| p.as_std_path())
+    };
+    let relative = |file: &Path| {
+        base_dir
+            .and_then(|d| file.strip_prefix(d).ok())
+            .unwrap_or(file)
+            .display()
+            .to_string()
+    };
+
+    let (pagerank, _) = centrality::pagerank(&graph, &opts.pagerank);
+    let betweenness = centrality::betweenness_centrality(&graph);
+    timings.push(("score", lap.elapsed()));
+    lap = Instant::now();
+    let kloc = |i: NodeIndex| {
+        opts.per_kloc
+            .then(|| pagerank[i.index()] * 1000.0 / graph[i].loc.max(1) as f64)
+    };
+    let key = |i: NodeIndex| kloc(i).unwrap_or(pagerank[i.index()]);
+
+    let mut nodes: Vec<NodeIndex> = graph.node_indices().collect();
+    nodes.sort_by(|&a, &b| {
+        centrality::by_score_desc(key(a), key(b)).then_with(|| graph[a].path.cmp(&graph[b].path))
+    });
+
+    let rows = nodes
+        .into_iter()
+        .take(opts.top)
+        .enumerate()
+        .map(|(rank, i)| {
+            let file = &graph[i].file;
+            let api_items = if opts.api_surface {
+                api_items(&graph, i, &pagerank)
+            } else {
+                vec![]
+            };
+            ModuleRow {
+                rank: rank + 1,
+                module: graph[i].path.clone(),
+                file: relative(file),
+                pagerank: pagerank[i.index()],
+                pagerank_per_kloc: kloc(i),
+                betweenness: betweenness[i.index()],
+                loc: graph[i].loc,
+                bytes: graph[i].bytes,
+                dependents: graph.neighbors_directed(i, Direction::Incoming).count(),
+                dependencies: graph.neighbors_directed(i, Direction::Outgoing).count(),
+                api_surface: opts.api_surface.then_some(api_items.len()),
+                api_items,
+            }
+        })
+        .collect();
+
+    let (package, version, target_name) = if opts.workspace {
+        let root = &metadata.workspace_root;
+        (
+            root.file_name().unwrap_or(root.as_str()).to_string(),
+            String::new(),
+            "workspace".to_string(),
+        )
+    } else {
+        (
+            package.name.to_string(),
+            package.version.to_string(),
+            target.name.clone(),
+        )
+    };
+    timings.push(("report", lap.elapsed()));
+
+    Ok(ModulesOut {
+        package,
+        version,
+        target: target_name,
+        backend: opts.backend,
+        timings,
+        nodes: graph.node_count(),
+        edges: graph.edge_count(),
+        rows,
+        crates: if opts.workspace {
+            roots.iter().map(|(name, _)| name.clone()).collect()
+        } else {
+            vec![]
+        },
+        unused: opts.report_unused.then(|| {
+            let parsed = graph.node_weights().map(|m| m.file.as_path()).collect();
+            let orphans = selected
+                .iter()
+                .flat_map(|(p, t)| orphan_files(p, t, &parsed))
+                .map(|f| relative(&f))
+                .collect();
+            // In a merged graph, other workspace crates may use `pub` items.
+            let bin = !opts.workspace && target.is_bin();
+            unused(&graph, &pagerank, bin, orphans)
+        }),
+        graph: opts.emit_graph.then(|| {
+            let (kept, original) = truncate::truncate(&graph, &pagerank, opts.caps);
+            GraphExport {
+                nodes: kept
+                    .node_indices()
+                    .map(|i| {
+                        let old = original[i.index()].index();
+                        ExportNode {
+                            id: i.index(),
+                            module: kept[i].path.clone(),
+                            file: relative(&kept[i].file),
+                            loc: kept[i].loc,
+                            pagerank: pagerank[old],
+                            betweenness: betweenness[old],
+                        }
+                    })
+                    .collect(),
+                edges: kept
+                    .edge_references()
+                    .map(|e| ExportEdge {
+                        from: e.source().index(),
+                        to: e.target().index(),
+                        refs: e.weight().refs,
+                        items: e.weight().items.iter().cloned().collect(),
+                    })
+                    .collect(),
+            }
+        }),
+        cycles: if opts.report_cycles {
+            cycles(&graph)
+        } else {
+            vec![]
+        },
+        warnings,
+    })
+}
+
+pub fn print_text(out: &ModulesOut) {
+    let per_kloc = out.rows.iter().any(|r| r.pagerank_per_kloc.is_some());
+    let subject = if out.crates.is_empty() {
+        format!("{} {} ({})", out.package, out.version, out.target)
+    } else {
+        format!("{} ({} crates)", out.package, out.crates.len())
+    };
+    println!(
+        "Modules of {} by {}:",
+        subject,
+        if per_kloc {
+            "Pagerank per 1000 lines"
+        } else {
+            "Pagerank"
+        }
+    );
+    println!("{:─<50}", "");
+    for row in &out.rows {
+        let api = row
+            .api_surface
+            .map(|n| format!(" {:>4} api", n))
+            .unwrap_or_default();
+        println!(
+            "{:3}. {:40} {:.6} {:>6} loc {:>4} in {:>4} out{}  {}",
+            row.rank,
+            row.module,
+            row.pagerank_per_kloc.unwrap_or(row.pagerank),
+            row.loc,
+            row.dependents,
+            row.dependencies,
+            api,
+            row.file
+        );
+    }
+    println!("\n{} modules, {} edges", out.nodes, out.edges);
+    for cycle in &out.cycles {
+        println!(
+            "\ncycle of {} modules: {}",
+            cycle.modules.len(),
+            cycle.modules.join(", ")
+        );
+        for edge in &cycle.edges {
+            println!("  {} -> {}: {}", edge.from, edge.to, edge.items.join(", "));
+        }
+    }
+    if let Some(unused) = &out.unused {
+        for (label, list) in [
+            ("unused modules", &unused.modules),
+            ("unused items", &unused.items),
+            ("orphan files", &unused.orphan_files),
+        ] {
+            if !list.is_empty() {
+                println!("\n{} ({}):", label, list.len());
+                for name in list {
+                    println!("  {}", name);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Workspaces nested under the root workspace (`--nested`).
+//!
+//! A directory with its own `[workspace]` is excluded from the enclosing
+//! workspace, so `cargo metadata` on the root never sees its crates.
+
+use crate::graph::DepGraph;
+use petgraph::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Directories never searched: build output and fixture data, which often
+/// holds throwaway workspaces of its own.
+const SKIP_DIRS: &[&str] = &["target", "fixtures", "testdata"];
+
+/// Directories under `root` (excluding `root` itself) whose Cargo.toml
+/// declares a `[workspace]`, sorted. Hidden directories and `SKIP_DIRS`
+/// are skipped, as is everything under a nested workspace once found.
+pub fn find(root: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    walk(root, root, usize::MAX, &mut found);
+    found.sort();
+    found
+}
+
+/// Like `find`, but only `depth` directories deep: cheap enough for the
+/// skipped-workspaces hint on every run without `--nested`.
+pub fn find_near(root: &Path, depth: usize) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    walk(root, root, depth, &mut found);
+    found.sort();
+    found
+}
+
+fn walk(root: &Path, dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+    if dir != root && declares_workspace(&dir.join("Cargo.toml")) {
+        found.push(dir.to_path_buf());
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || SKIP_DIRS.contains(&name.as_ref()) {
+            continue;
+        }
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            walk(root, &entry.path(), depth - 1, found);
+        }
+    }
+}
+
+fn declares_workspace(manifest: &Path) -> bool {
+    std::fs::read_to_string(manifest).is_ok_and(|text| {
+        text.lines()
+            .any(|line| line.trim() == "[workspace]" || line.trim().starts_with("[workspace."))
+    })
+}
+
+/// `dir` relative to `root`, for display and provenance.
+pub fn label(root: &Path, dir: &Path) -> String {
+    dir.strip_prefix(root).unwrap_or(dir).display().to_string()
+}
+
+/// Union of the root graph and nested-workspace graphs, keyed by manifest
+/// path. Members of a nested workspace are tagged with its label.
+pub fn merge(root: DepGraph, nested: Vec<(String, DepGraph)>) -> DepGraph {
+    let mut merged = DepGraph::new();
+    let mut nodes: HashMap<PathBuf, NodeIndex> = HashMap::new();
+
+    let graphs = std::iter::once((None, root)).chain(nested.into_iter().map(|(l, g)| (Some(l), g)));
+    for (label, graph) in graphs {
+        let mut map = Vec::with_capacity(graph.node_count());
+        for pkg in graph.node_weights() {
+            let idx = *nodes
+                .entry(pkg.manifest_path.clone())
+                .or_insert_with(|| merged.add_node(pkg.clone()));
+            if pkg.workspace_member {
+                let node = &mut merged[idx];
+                node.workspace_member = true;
+                node.workspace = node.workspace.take().or(label.clone());
+            }
+            map.push(idx);
+        }
+        for edge in graph.edge_references() {
+            let (from, to) = (map[edge.source().index()], map[edge.target().index()]);
+            let dep = edge.weight();
+            match merged.find_edge(from, to) {
+                Some(e) => {
+                    let existing = &mut merged[e];
+                    for kind in &dep.kinds {
+                        if !existing.kinds.contains(kind) {
+                            existing.kinds.push(*kind);
+                        }
+                    }
+                    existing.weight = existing.weight.max(dep.weight);
+                }
+                None => {
+                    merged.add_edge(from, to, dep.clone());
+                }
+            }
+        }
+    }
+    merged
+}
@@ -0,0 +1,92 @@
+//! `pkgrank orphans`: workspace crates disconnected from the rest of the workspace.
+
+use crate::git;
+use crate::graph::DepGraph;
+use petgraph::prelude::*;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct OrphanRow {
+    pub name: String,
+    pub version: String,
+    /// Crate directory, relative to the workspace root.
+    pub dir: String,
+    /// Days since the last commit touching the crate directory.
+    pub last_commit_days: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+/// Workspace members with no dependents and no dependencies among other
+/// workspace members. With `stale_days`, only crates without a commit in
+/// that many days are kept.
+pub fn orphans(graph: &DepGraph, workspace_root: &Path, stale_days: Option<u64>) -> Vec<OrphanRow> {
+    let members: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|&i| graph[i].workspace_member)
+        .collect();
+    if members.len() < 2 {
+        return vec![];
+    }
+
+    let mut rows: Vec<OrphanRow> = members
+        .into_iter()
+        .filter(|&i| {
+            !graph
+                .neighbors_undirected(i)
+                .any(|j| j != i && graph[j].workspace_member)
+        })
+        .filter_map(|i| {
+            let pkg = &graph[i];
+            let dir = pkg.manifest_path.parent().unwrap_or(workspace_root);
+            let last_commit_days = git::last_commit_age_days(dir);
+            if let Some(days) = stale_days
+                && last_commit_days.is_some_and(|d| d < days)
+            {
+                return None;
+            }
+            Some(OrphanRow {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                dir: dir
+                    .strip_prefix(workspace_root)
+                    .unwrap_or(dir)
+                    .display()
+                    .to_string(),
+                last_commit_days,
+                owner: pkg.owner.clone(),
+            })
+        })
+        .collect();
+
+    rows.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version)));
+    rows
+}
+
+pub fn print_text(rows: &[OrphanRow]) {
+    println!("Orphan workspace crates (no workspace dependents or dependencies):");
+    println!("{:─<50}", "");
+    if rows.is_empty() {
+        println!("  none");
+        return;
+    }
+    for (i, row) in rows.iter().enumerate() {
+        let label = format!("{} {}", row.name, row.version);
+        let activity = match row.last_commit_days {
+            Some(days) => format!("last commit {}d ago", days),
+            None => "no git history".to_string(),
+        };
+        let owner = row.owner.as_deref().unwrap_or("");
+        let line = format!(
+            "{:3}. {:40} {:30} {:20} {}",
+            i + 1,
+            label,
+            row.dir,
+            activity,
+            owner
+        );
+        println!("{}", line.trim_end());
+    }
+}
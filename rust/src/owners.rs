@@ -0,0 +1,123 @@
+//! `pkgrank owners`: per-owner rollup of architectural load.
+
+use crate::centrality::{self, PageRankConfig};
+use crate::git;
+use crate::graph::DepGraph;
+use petgraph::prelude::*;
+use petgraph::visit::{Bfs, Walker};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+const UNOWNED: &str = "(unowned)";
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct OwnerRollup {
+    pub owner: String,
+    pub crates: Vec<String>,
+    /// Sum of the owned crates' pagerank.
+    pub pagerank_mass: f64,
+    /// Workspace crates outside this owner that depend on its crates.
+    pub dependents: usize,
+    /// Distinct third-party packages directly depended on.
+    pub direct_third_party: usize,
+    /// Distinct third-party packages in the transitive closure.
+    pub third_party_surface: usize,
+    /// Days since the most recent commit touching any owned crate.
+    pub last_commit_days: Option<u64>,
+    /// Days since the last commit of the least recently touched crate.
+    pub stalest_crate_days: Option<u64>,
+}
+
+pub fn rollup(graph: &DepGraph, config: &PageRankConfig) -> Vec<OwnerRollup> {
+    let (pagerank, _) = centrality::pagerank(graph, config);
+
+    let mut by_owner: BTreeMap<&str, Vec<NodeIndex>> = BTreeMap::new();
+    for i in graph.node_indices().filter(|&i| graph[i].workspace_member) {
+        let owner = graph[i].owner.as_deref().unwrap_or(UNOWNED);
+        by_owner.entry(owner).or_default().push(i);
+    }
+
+    let mut rows: Vec<OwnerRollup> = by_owner
+        .into_iter()
+        .map(|(owner, members)| {
+            let owned: BTreeSet<NodeIndex> = members.iter().copied().collect();
+
+            let mut direct = BTreeSet::new();
+            let mut surface = BTreeSet::new();
+            let mut dependents = BTreeSet::new();
+            for &m in &members {
+                for j in graph.neighbors_directed(m, Direction::Outgoing) {
+                    if !graph[j].workspace_member {
+                        direct.insert(j);
+                    }
+                }
+                for j in Bfs::new(graph, m).iter(graph) {
+                    if !graph[j].workspace_member {
+                        surface.insert(j);
+                    }
+                }
+                for j in graph.neighbors_directed(m, Direction::Incoming) {
+                    if graph[j].workspace_member && !owned.contains(&j) {
+                        dependents.insert(j);
+                    }
+                }
+            }
+
+            let ages: Vec<Option<u64>> = members
+                .iter()
+                .map(|&m| {
+                    graph[m]
+                        .manifest_path
+                        .parent()
+                        .and_then(git::last_commit_age_days)
+                })
+                .collect();
+
+            let mut crates: Vec<String> = members.iter().map(|&m| graph[m].name.clone()).collect();
+            crates.sort();
+
+            OwnerRollup {
+                owner: owner.to_string(),
+                crates,
+                pagerank_mass: members.iter().map(|m| pagerank[m.index()]).sum(),
+                dependents: dependents.len(),
+                direct_third_party: direct.len(),
+                third_party_surface: surface.len(),
+                last_commit_days: ages.iter().flatten().min().copied(),
+                stalest_crate_days: ages.iter().flatten().max().copied(),
+            }
+        })
+        .collect();
+
+    rows.sort_by(|a, b| {
+        centrality::by_score_desc(a.pagerank_mass, b.pagerank_mass)
+            .then_with(|| a.owner.cmp(&b.owner))
+    });
+    rows
+}
+
+pub fn print_text(rows: &[OwnerRollup]) {
+    println!(
+        "{:30} {:>6} {:>10} {:>10} {:>8} {:>8} {:>10}",
+        "owner", "crates", "pagerank", "dependents", "direct", "surface", "last/stale"
+    );
+    println!("{:─<90}", "");
+    for row in rows {
+        let days = |d: Option<u64>| d.map_or("-".to_string(), |d| format!("{}d", d));
+        println!(
+            "{:30} {:>6} {:>10.6} {:>10} {:>8} {:>8} {:>10}",
+            row.owner,
+            row.crates.len(),
+            row.pagerank_mass,
+            row.dependents,
+            row.direct_third_party,
+            row.third_party_surface,
+            format!(
+                "{}/{}",
+                days(row.last_commit_days),
+                days(row.stalest_crate_days)
+            )
+        );
+    }
+}
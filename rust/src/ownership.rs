@@ -0,0 +1,101 @@
+//! `pkgrank ownership`: git author concentration (bus factor) per workspace crate.
+
+use crate::centrality::{self, PageRankConfig};
+use crate::git;
+use crate::graph::DepGraph;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct OwnershipRow {
+    pub name: String,
+    pub version: String,
+    pub pagerank: f64,
+    pub commits: usize,
+    pub authors: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_author: Option<String>,
+    /// Share of commits by the most active author.
+    pub top_author_share: f64,
+    /// Fewest authors who together made more than half the commits.
+    pub bus_factor: usize,
+    /// CODEOWNERS owner, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+/// Workspace crates with git history, by descending pagerank.
+pub fn ownership(
+    graph: &DepGraph,
+    days: Option<u64>,
+    config: &PageRankConfig,
+) -> Vec<OwnershipRow> {
+    let (pagerank, _) = centrality::pagerank(graph, config);
+
+    let mut rows: Vec<OwnershipRow> = graph
+        .node_indices()
+        .filter(|&i| graph[i].workspace_member)
+        .filter_map(|i| {
+            let authors = git::authors(graph[i].manifest_path.parent()?, days)?;
+            let commits: usize = authors.iter().map(|(_, n)| n).sum();
+            if commits == 0 {
+                return None;
+            }
+            let mut covered = 0;
+            let bus_factor = authors
+                .iter()
+                .take_while(|(_, n)| {
+                    let below_half = covered * 2 <= commits;
+                    covered += n;
+                    below_half
+                })
+                .count();
+            Some(OwnershipRow {
+                name: graph[i].name.clone(),
+                version: graph[i].version.clone(),
+                pagerank: pagerank[i.index()],
+                commits,
+                authors: authors.len(),
+                top_author: authors.first().map(|(a, _)| a.clone()),
+                top_author_share: authors[0].1 as f64 / commits as f64,
+                bus_factor,
+                owner: graph[i].owner.clone(),
+            })
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        centrality::by_score_desc(a.pagerank, b.pagerank)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.version.cmp(&b.version))
+    });
+    rows
+}
+
+pub fn print_text(rows: &[OwnershipRow]) {
+    println!(
+        "{:30} {:>10} {:>7} {:>7} {:>6} {:>4}  top author",
+        "crate", "pagerank", "commits", "authors", "top%", "bus"
+    );
+    println!("{:─<90}", "");
+    for row in rows {
+        let line = format!(
+            "{:30} {:>10.6} {:>7} {:>7} {:>5.0}% {:>4}  {}",
+            row.name,
+            row.pagerank,
+            row.commits,
+            row.authors,
+            row.top_author_share * 100.0,
+            row.bus_factor,
+            row.top_author.as_deref().unwrap_or("")
+        );
+        println!("{}", line.trim_end());
+    }
+    let single: Vec<&str> = rows
+        .iter()
+        .filter(|r| r.bus_factor == 1)
+        .map(|r| r.name.as_str())
+        .collect();
+    if !single.is_empty() {
+        println!("\nBus factor 1: {}", single.join(", "));
+    }
+}
@@ -0,0 +1,243 @@
+//! `pkgrank sbom`: CycloneDX / SPDX export of the resolved graph, with
+//! pkgrank centrality attached as properties.
+
+use crate::artifacts;
+use crate::centrality::{self, PageRankConfig};
+use crate::graph::{DepGraph, Package};
+use crate::time;
+use crate::truncate::{self, Caps};
+use clap::ValueEnum;
+use petgraph::prelude::*;
+use serde_json::{Value, json};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SbomFormat {
+    /// CycloneDX 1.5 JSON
+    Cyclonedx,
+    /// SPDX 2.3 JSON
+    Spdx,
+}
+
+const TOOL: &str = concat!("pkgrank-", env!("CARGO_PKG_VERSION"));
+
+/// Scores are computed on the whole graph; `caps` then limits which
+/// packages and dependency edges are exported.
+pub fn sbom(
+    graph: &DepGraph,
+    name: &str,
+    format: SbomFormat,
+    caps: Caps,
+    config: &PageRankConfig,
+) -> Value {
+    let (pagerank, _) = centrality::pagerank(graph, config);
+    let (kept, original) = truncate::truncate(graph, &pagerank, caps);
+    let scores: Vec<Scores> = original
+        .iter()
+        .map(|&i| Scores {
+            pagerank: pagerank[i.index()],
+            dependents: graph.neighbors_directed(i, Direction::Incoming).count(),
+        })
+        .collect();
+    match format {
+        SbomFormat::Cyclonedx => cyclonedx(&kept, name, &scores),
+        SbomFormat::Spdx => spdx(&kept, name, &scores),
+    }
+}
+
+/// Per-package properties, from the full graph.
+struct Scores {
+    pagerank: f64,
+    dependents: usize,
+}
+
+fn from_registry(pkg: &Package) -> bool {
+    pkg.source
+        .as_deref()
+        .is_some_and(|s| s.starts_with("registry+") || s.starts_with("sparse+"))
+}
+
+/// `pkg:cargo` names a registry crate, so path and git packages get none.
+fn purl(pkg: &Package) -> Option<String> {
+    from_registry(pkg).then(|| format!("pkg:cargo/{}@{}", pkg.name, pkg.version))
+}
+
+/// The package ID: a path or git package can share name and version with a
+/// registry one.
+fn bom_ref(pkg: &Package) -> String {
+    pkg.id.clone()
+}
+
+/// Cargo still accepts the legacy `MIT/Apache-2.0` form, which is not a
+/// valid SPDX expression.
+fn license_expression(license: &str) -> String {
+    license
+        .split('/')
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+fn cyclonedx(graph: &DepGraph, name: &str, scores: &[Scores]) -> Value {
+    let components: Vec<Value> = graph
+        .node_indices()
+        .map(|i| {
+            let pkg = &graph[i];
+            let mut component = json!({
+                "type": if pkg.workspace_member { "application" } else { "library" },
+                "bom-ref": bom_ref(pkg),
+                "name": pkg.name,
+                "version": pkg.version,
+                "properties": [
+                    { "name": "pkgrank:pagerank", "value": format!("{:.6}", scores[i.index()].pagerank) },
+                    { "name": "pkgrank:dependents", "value": scores[i.index()].dependents.to_string() },
+                    { "name": "pkgrank:workspace_member", "value": pkg.workspace_member.to_string() },
+                ],
+            });
+            if let Some(purl) = purl(pkg) {
+                component["purl"] = json!(purl);
+            }
+            if let Some(license) = &pkg.license {
+                component["licenses"] = json!([{ "expression": license_expression(license) }]);
+            }
+            component
+        })
+        .collect();
+
+    let dependencies: Vec<Value> = graph
+        .node_indices()
+        .map(|i| {
+            let mut depends_on: Vec<String> = graph
+                .neighbors_directed(i, Direction::Outgoing)
+                .map(|j| bom_ref(&graph[j]))
+                .collect();
+            depends_on.sort();
+            depends_on.dedup();
+            json!({ "ref": bom_ref(&graph[i]), "dependsOn": depends_on })
+        })
+        .collect();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": time::format_rfc3339(time::now_secs()),
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "pkgrank",
+                    "version": env!("CARGO_PKG_VERSION"),
+                }],
+            },
+            "component": { "type": "application", "name": name },
+        },
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+/// SPDX identifiers allow only letters, digits, `.` and `-`. A hash of the
+/// package ID keeps same-name-and-version packages apart.
+fn spdx_id(pkg: &Package) -> String {
+    let raw = format!(
+        "{}-{}-{}",
+        pkg.name,
+        pkg.version,
+        artifacts::content_hash(pkg.id.as_bytes())
+    );
+    let id: String = raw
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("SPDXRef-Package-{}", id)
+}
+
+fn spdx(graph: &DepGraph, name: &str, scores: &[Scores]) -> Value {
+    let now = time::now_secs();
+    let created = time::format_rfc3339(now);
+
+    let packages: Vec<Value> = graph
+        .node_indices()
+        .map(|i| {
+            let pkg = &graph[i];
+            let download = if from_registry(pkg) {
+                format!(
+                    "https://crates.io/api/v1/crates/{}/{}/download",
+                    pkg.name, pkg.version
+                )
+            } else {
+                "NOASSERTION".to_string()
+            };
+            let mut package = json!({
+                "name": pkg.name,
+                "SPDXID": spdx_id(pkg),
+                "versionInfo": pkg.version,
+                "downloadLocation": download,
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": pkg
+                    .license
+                    .as_deref()
+                    .map_or("NOASSERTION".to_string(), license_expression),
+                "copyrightText": "NOASSERTION",
+                "annotations": [{
+                    "annotationType": "OTHER",
+                    "annotator": format!("Tool: {}", TOOL),
+                    "annotationDate": created,
+                    "comment": format!(
+                        "pkgrank:pagerank={:.6} pkgrank:dependents={}",
+                        scores[i.index()].pagerank,
+                        scores[i.index()].dependents
+                    ),
+                }],
+            });
+            if let Some(purl) = purl(pkg) {
+                package["externalRefs"] = json!([{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": purl,
+                }]);
+            }
+            package
+        })
+        .collect();
+
+    let mut relationships: Vec<Value> = graph
+        .node_indices()
+        .filter(|&i| graph[i].workspace_member)
+        .map(|i| {
+            json!({
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": spdx_id(&graph[i]),
+            })
+        })
+        .collect();
+    relationships.extend(graph.edge_references().map(|e| {
+        json!({
+            "spdxElementId": spdx_id(&graph[e.source()]),
+            "relationshipType": "DEPENDS_ON",
+            "relatedSpdxElement": spdx_id(&graph[e.target()]),
+        })
+    }));
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": format!("https://spdx.org/spdxdocs/{}-{}-{}", TOOL, name, now),
+        "creationInfo": {
+            "created": created,
+            "creators": [format!("Tool: {}", TOOL)],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
@@ -0,0 +1,89 @@
+//! `pkgrank schema`: JSON Schema for each `--json` payload.
+
+use crate::analyze::{AnalyzeOut, StreamLine, WorkspaceGraph};
+use crate::artifacts::{ArtifactRow, Envelope, Provenance};
+use crate::cochange::CoChangeOut;
+use crate::compare::CompareOut;
+use crate::digest::DigestState;
+use crate::dupes::Dupe;
+use crate::features::FeaturesImpactOut;
+use crate::fixture::Fixture;
+use crate::gateways::GatewayRow;
+use crate::gc::GcOut;
+use crate::impact::ImpactOut;
+use crate::license::LicenseReport;
+use crate::modules::{GraphExport, ModulesOut};
+use crate::orphans::OrphanRow;
+use crate::owners::OwnerRollup;
+use crate::ownership::OwnershipRow;
+use crate::snapshot::SnapshotManifest;
+use crate::why::WhyOut;
+use clap::ValueEnum;
+use schemars::{Schema, schema_for};
+
+/// Output payloads with a published schema, named after the producing command.
+/// Files only ever written as artifacts are described with their
+/// provenance envelope; `--json` payloads are described bare (`--out`
+/// wraps them the same way).
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Payload {
+    Analyze,
+    /// The `workspace_graph.json` written by `analyze --out`, in its envelope
+    WorkspaceGraph,
+    /// One line of `analyze --stream`
+    AnalyzeStream,
+    Why,
+    Impact,
+    Orphans,
+    FeaturesImpact,
+    Dupes,
+    Gateways,
+    Owners,
+    Modules,
+    /// The file written by `modules --emit-graph`
+    ModulesGraph,
+    Ownership,
+    CoChange,
+    /// The state file written by `digest`, in its envelope
+    DigestState,
+    /// The `licenses.json` written by `analyze --licenses --out`, in its
+    /// envelope
+    Licenses,
+    Artifacts,
+    /// The `provenance` header of every registered artifact (not of
+    /// `fixture.json` or `--emit-graph` files, which have none)
+    Provenance,
+    /// The `fixture.json` written by `gen-fixture`
+    Fixture,
+    /// The `snapshot.json` manifest written by `snapshot`, in its envelope
+    Snapshot,
+    CompareRuns,
+    Gc,
+}
+
+pub fn schema(payload: Payload) -> Schema {
+    match payload {
+        Payload::Analyze => schema_for!(AnalyzeOut),
+        Payload::WorkspaceGraph => schema_for!(Envelope<WorkspaceGraph>),
+        Payload::AnalyzeStream => schema_for!(StreamLine),
+        Payload::Why => schema_for!(WhyOut),
+        Payload::Impact => schema_for!(ImpactOut),
+        Payload::Orphans => schema_for!(Vec<OrphanRow>),
+        Payload::FeaturesImpact => schema_for!(FeaturesImpactOut),
+        Payload::Dupes => schema_for!(Vec<Dupe>),
+        Payload::Gateways => schema_for!(Vec<GatewayRow>),
+        Payload::Owners => schema_for!(Vec<OwnerRollup>),
+        Payload::Modules => schema_for!(ModulesOut),
+        Payload::ModulesGraph => schema_for!(GraphExport),
+        Payload::Ownership => schema_for!(Vec<OwnershipRow>),
+        Payload::CoChange => schema_for!(CoChangeOut),
+        Payload::DigestState => schema_for!(Envelope<DigestState>),
+        Payload::Licenses => schema_for!(Envelope<LicenseReport>),
+        Payload::Artifacts => schema_for!(Vec<ArtifactRow>),
+        Payload::Provenance => schema_for!(Provenance),
+        Payload::Fixture => schema_for!(Fixture),
+        Payload::Snapshot => schema_for!(Envelope<SnapshotManifest>),
+        Payload::CompareRuns => schema_for!(CompareOut),
+        Payload::Gc => schema_for!(GcOut),
+    }
+}
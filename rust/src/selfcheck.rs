@@ -0,0 +1,114 @@
+//! `pkgrank selfcheck`: rank pkgrank's own dependency graph and compare it
+//! to a committed golden result.
+
+use crate::centrality::{self, PageRankConfig};
+use crate::graph::{self, GraphOptions};
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Golden result, relative to pkgrank's manifest directory.
+const GOLDEN_FILE: &str = "selfcheck.golden.json";
+const GOLDEN: &str = include_str!("../selfcheck.golden.json");
+const TOP: usize = 20;
+/// Allowed score difference; runs are deterministic, so this only absorbs
+/// floating-point formatting.
+const TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelfcheckResult {
+    pub nodes: usize,
+    pub edges: usize,
+    pub iterations: usize,
+    pub top: Vec<SelfcheckRow>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelfcheckRow {
+    pub name: String,
+    /// Informational only; versions are pinned by the committed Cargo.lock,
+    /// and a deliberate `cargo update` is followed by `selfcheck --bless`.
+    pub version: String,
+    pub score: f64,
+}
+
+pub fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Run the default pipeline (normal deps, default PageRank) on pkgrank itself.
+pub fn run(dir: &Path) -> anyhow::Result<SelfcheckResult> {
+    if !dir.join("Cargo.toml").is_file() {
+        bail!(
+            "pkgrank sources not found at {}; selfcheck needs the source checkout it was built from",
+            dir.display()
+        );
+    }
+    let metadata = graph::load_metadata(&dir.to_string_lossy(), None, None)?;
+    let graph = graph::build_graph(&metadata, GraphOptions::default());
+    let (scores, convergence) = centrality::pagerank(&graph, &PageRankConfig::default());
+
+    let mut nodes: Vec<_> = graph.node_indices().collect();
+    nodes.sort_by(|&a, &b| {
+        centrality::by_score_desc(scores[a.index()], scores[b.index()])
+            .then_with(|| graph[a].name.cmp(&graph[b].name))
+            .then_with(|| graph[a].version.cmp(&graph[b].version))
+    });
+
+    Ok(SelfcheckResult {
+        nodes: graph.node_count(),
+        edges: graph.edge_count(),
+        iterations: convergence.iterations,
+        top: nodes
+            .into_iter()
+            .take(TOP)
+            .map(|i| SelfcheckRow {
+                name: graph[i].name.clone(),
+                version: graph[i].version.clone(),
+                score: scores[i.index()],
+            })
+            .collect(),
+    })
+}
+
+/// Differences from the golden result, one line each; empty when it matches.
+pub fn compare(result: &SelfcheckResult) -> anyhow::Result<Vec<String>> {
+    let golden: SelfcheckResult = serde_json::from_str(GOLDEN)?;
+    let mut diffs = Vec::new();
+    for (what, got, want) in [
+        ("nodes", result.nodes, golden.nodes),
+        ("edges", result.edges, golden.edges),
+        ("iterations", result.iterations, golden.iterations),
+    ] {
+        if got != want {
+            diffs.push(format!("{}: got {}, expected {}", what, got, want));
+        }
+    }
+    for i in 0..result.top.len().max(golden.top.len()) {
+        match (result.top.get(i), golden.top.get(i)) {
+            (Some(got), Some(want))
+                if got.name == want.name && (got.score - want.score).abs() <= TOLERANCE => {}
+            (got, want) => {
+                let show = |r: Option<&SelfcheckRow>| {
+                    r.map_or("-".to_string(), |r| {
+                        format!("{} {} {:.9}", r.name, r.version, r.score)
+                    })
+                };
+                diffs.push(format!(
+                    "rank {}: got {}, expected {}",
+                    i + 1,
+                    show(got),
+                    show(want)
+                ));
+            }
+        }
+    }
+    Ok(diffs)
+}
+
+/// Overwrite the golden file with `result`.
+pub fn bless(dir: &Path, result: &SelfcheckResult) -> anyhow::Result<PathBuf> {
+    let path = dir.join(GOLDEN_FILE);
+    std::fs::write(&path, serde_json::to_string_pretty(result)? + "\n")?;
+    Ok(path)
+}
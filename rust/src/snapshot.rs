@@ -0,0 +1,108 @@
+//! `pkgrank snapshot`: freeze the current artifact set under a label for
+//! later before/after comparisons.
+
+use crate::artifacts::{self, Artifact};
+use crate::{git, time};
+use anyhow::{Context, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Subdirectory of the artifact directory holding one directory per snapshot.
+pub const RUNS_DIR: &str = "runs";
+
+/// Written as `snapshot.json` inside each snapshot directory.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SnapshotManifest {
+    pub label: String,
+    pub created_at: u64,
+    /// `HEAD` of the repository containing the artifact directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    pub files: Vec<SnapshotFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SnapshotFile {
+    pub artifact: String,
+    pub file: String,
+    pub bytes: u64,
+    /// See `artifacts::content_hash`.
+    pub hash: String,
+}
+
+/// Copy every artifact present in `dir` into `dir/runs/<label>/` and write
+/// its manifest. The label defaults to `git describe` of `dir` plus the
+/// current UTC time (just the time outside a repository).
+pub fn snapshot(dir: &Path, label: Option<&str>) -> anyhow::Result<(PathBuf, SnapshotManifest)> {
+    let label = match label {
+        Some(label) => {
+            if label.is_empty() || label == "." || label == ".." || label.contains(['/', '\\']) {
+                bail!("invalid snapshot label `{}`", label);
+            }
+            label.to_string()
+        }
+        None => {
+            let stamp = time::format_rfc3339(time::now_secs()).replace([':', '-'], "");
+            match git::describe(dir) {
+                // Tags may contain `/` (`release/1.2`).
+                Some(describe) => format!("{}-{}", describe.replace(['/', '\\'], "-"), stamp),
+                None => stamp,
+            }
+        }
+    };
+
+    let present: Vec<(Artifact, Vec<u8>)> = artifacts::ALL
+        .iter()
+        .filter(|a| a.file != artifacts::SNAPSHOT.file)
+        .filter_map(|&a| std::fs::read(a.path(dir)).ok().map(|bytes| (a, bytes)))
+        .collect();
+    if present.is_empty() {
+        bail!(
+            "no artifacts in {}; write some with `analyze --out` or `modules --out`",
+            dir.display()
+        );
+    }
+
+    let target = dir.join(RUNS_DIR).join(&label);
+    if target.exists() {
+        bail!(
+            "snapshot `{}` already exists at {}",
+            label,
+            target.display()
+        );
+    }
+    std::fs::create_dir_all(&target).with_context(|| format!("creating {}", target.display()))?;
+
+    let mut files = Vec::with_capacity(present.len());
+    for (artifact, bytes) in present {
+        let path = artifact.path(&target);
+        std::fs::write(&path, &bytes).with_context(|| format!("writing {}", path.display()))?;
+        files.push(SnapshotFile {
+            artifact: artifact.name.to_string(),
+            file: artifact.file.to_string(),
+            bytes: bytes.len() as u64,
+            hash: artifacts::content_hash(&bytes),
+        });
+    }
+
+    let manifest = SnapshotManifest {
+        label,
+        created_at: time::now_secs(),
+        git_commit: git::head_sha(dir),
+        files,
+    };
+    artifacts::SNAPSHOT.write(&target, None, &manifest)?;
+    Ok((target, manifest))
+}
+
+pub fn print_text(target: &Path, manifest: &SnapshotManifest) {
+    println!("Snapshot {} in {}:", manifest.label, target.display());
+    println!("{:─<50}", "");
+    for file in &manifest.files {
+        println!("{:21} {:10} bytes  {}", file.file, file.bytes, file.hash);
+    }
+    if let Some(commit) = &manifest.git_commit {
+        println!("commit {}", commit);
+    }
+}
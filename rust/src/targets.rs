@@ -0,0 +1,127 @@
+//! Merging per-target dependency graphs (`--targets`).
+
+use crate::graph::{Dep, DepGraph};
+use petgraph::prelude::*;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Packages resolved for only one of the requested targets.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TargetExclusive {
+    pub target: String,
+    /// `name@version`, sorted.
+    pub packages: Vec<String>,
+}
+
+/// Union of per-target graphs, keyed by package ID. Nodes and edges
+/// record every target they were resolved for.
+pub fn merge(graphs: Vec<(String, DepGraph)>) -> DepGraph {
+    let mut merged = DepGraph::new();
+    let mut nodes: HashMap<String, NodeIndex> = HashMap::new();
+
+    for (target, graph) in graphs {
+        let mut map = Vec::with_capacity(graph.node_count());
+        for pkg in graph.node_weights() {
+            let idx = *nodes
+                .entry(pkg.id.clone())
+                .or_insert_with(|| merged.add_node(pkg.clone()));
+            merged[idx].targets.push(target.clone());
+            map.push(idx);
+        }
+        for edge in graph.edge_references() {
+            let (from, to) = (map[edge.source().index()], map[edge.target().index()]);
+            let dep = edge.weight();
+            match merged.find_edge(from, to) {
+                Some(e) => {
+                    let existing = &mut merged[e];
+                    for kind in &dep.kinds {
+                        if !existing.kinds.contains(kind) {
+                            existing.kinds.push(*kind);
+                        }
+                    }
+                    existing.weight = existing.weight.max(dep.weight);
+                    existing.targets.push(target.clone());
+                }
+                None => {
+                    merged.add_edge(
+                        from,
+                        to,
+                        Dep {
+                            targets: vec![target.clone()],
+                            ..dep.clone()
+                        },
+                    );
+                }
+            }
+        }
+    }
+    merged
+}
+
+/// Per target, the packages no other requested target resolves.
+pub fn exclusive(graph: &DepGraph, targets: &[String]) -> Vec<TargetExclusive> {
+    targets
+        .iter()
+        .map(|target| {
+            let mut packages: Vec<String> = graph
+                .node_weights()
+                .filter(|pkg| pkg.targets == [target.as_str()])
+                .map(|pkg| format!("{}@{}", pkg.name, pkg.version))
+                .collect();
+            packages.sort();
+            TargetExclusive {
+                target: target.clone(),
+                packages,
+            }
+        })
+        .filter(|t| !t.packages.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Package;
+
+    fn package(id: &str) -> Package {
+        Package {
+            id: id.to_string(),
+            name: "util".to_string(),
+            version: "0.1.0".to_string(),
+            manifest_path: Default::default(),
+            license: None,
+            source: None,
+            owner: None,
+            workspace_member: false,
+            workspace: None,
+            targets: vec![],
+        }
+    }
+
+    #[test]
+    fn same_name_and_version_stay_apart() {
+        let mut a = DepGraph::new();
+        a.add_node(package("git+file:///util#0.1.0"));
+        let mut b = DepGraph::new();
+        b.add_node(package("registry+https://example#util@0.1.0"));
+        b.add_node(package("git+file:///util#0.1.0"));
+
+        let merged = merge(vec![("a".to_string(), a), ("b".to_string(), b)]);
+        let mut targets: Vec<(&str, Vec<String>)> = merged
+            .node_weights()
+            .map(|p| (p.id.as_str(), p.targets.clone()))
+            .collect();
+        targets.sort();
+        assert_eq!(
+            targets,
+            [
+                (
+                    "git+file:///util#0.1.0",
+                    vec!["a".to_string(), "b".to_string()]
+                ),
+                ("registry+https://example#util@0.1.0", vec!["b".to_string()]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Size caps for graph exports (`--max-nodes`, `--max-edges`).
+
+use crate::centrality::{self, Weighted};
+use petgraph::prelude::*;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Caps {
+    pub max_nodes: Option<usize>,
+    pub max_edges: Option<usize>,
+}
+
+/// The subgraph of the `max_nodes` highest-scored nodes and the edges among
+/// them. If that still has more than `max_edges` edges, the heaviest are
+/// kept, ties going to edges between higher-scored nodes. Also returns,
+/// per kept node, its index in `graph`.
+pub fn truncate<N: Clone, E: Clone + Weighted>(
+    graph: &DiGraph<N, E>,
+    scores: &[f64],
+    caps: Caps,
+) -> (DiGraph<N, E>, Vec<NodeIndex>) {
+    let mut nodes: Vec<NodeIndex> = graph.node_indices().collect();
+    if let Some(max) = caps.max_nodes
+        && nodes.len() > max
+    {
+        nodes.sort_by(|&a, &b| {
+            centrality::by_score_desc(scores[a.index()], scores[b.index()]).then(a.cmp(&b))
+        });
+        nodes.truncate(max);
+        nodes.sort();
+    }
+
+    let mut kept = DiGraph::with_capacity(nodes.len(), 0);
+    let mut map = vec![None; graph.node_count()];
+    for &i in &nodes {
+        map[i.index()] = Some(kept.add_node(graph[i].clone()));
+    }
+
+    let mut edges: Vec<EdgeIndex> = graph
+        .edge_indices()
+        .filter(|&e| {
+            let (a, b) = graph.edge_endpoints(e).expect("edge index from this graph");
+            map[a.index()].is_some() && map[b.index()].is_some()
+        })
+        .collect();
+    if let Some(max) = caps.max_edges
+        && edges.len() > max
+    {
+        let endpoint_score = |e: EdgeIndex| {
+            let (a, b) = graph.edge_endpoints(e).expect("edge index from this graph");
+            scores[a.index()] + scores[b.index()]
+        };
+        edges.sort_by(|&x, &y| {
+            centrality::by_score_desc(graph[x].weight(), graph[y].weight())
+                .then_with(|| centrality::by_score_desc(endpoint_score(x), endpoint_score(y)))
+                .then(x.cmp(&y))
+        });
+        edges.truncate(max);
+        edges.sort();
+    }
+    for e in edges {
+        let (a, b) = graph.edge_endpoints(e).expect("edge index from this graph");
+        kept.add_edge(
+            map[a.index()].expect("kept endpoint"),
+            map[b.index()].expect("kept endpoint"),
+            graph[e].clone(),
+        );
+    }
+
+    (kept, nodes)
+}
@@ -0,0 +1,161 @@
+//! `pkgrank watch`: re-run analysis when manifests or lockfiles change.
+
+use crate::analyze::AnalyzeOut;
+use crate::time;
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Watch `root` and call `rerun` after each burst of relevant changes,
+/// printing the delta against the previous result. Runs until interrupted.
+pub fn watch(
+    root: &Path,
+    debounce: Duration,
+    mut previous: AnalyzeOut,
+    mut rerun: impl FnMut() -> anyhow::Result<AnalyzeOut>,
+) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+    eprintln!(
+        "watching {} for Cargo.toml / Cargo.lock changes",
+        root.display()
+    );
+
+    loop {
+        let mut changed: Vec<PathBuf> = relevant_paths(rx.recv()?);
+        // Editors and cargo touch several files at once; wait for the burst to end.
+        while let Ok(event) = rx.recv_timeout(debounce) {
+            changed.extend(relevant_paths(event));
+        }
+        if changed.is_empty() {
+            continue;
+        }
+        changed.sort();
+        changed.dedup();
+
+        let names: Vec<String> = changed
+            .iter()
+            .map(|p| p.strip_prefix(root).unwrap_or(p).display().to_string())
+            .collect();
+        println!(
+            "\n[{}] changed: {}",
+            time::format_rfc3339(time::now_secs()),
+            names.join(", ")
+        );
+
+        match rerun() {
+            Ok(current) => {
+                print_delta(&previous, &current);
+                previous = current;
+            }
+            Err(err) => eprintln!("error: {:#}", err),
+        }
+    }
+}
+
+fn relevant_paths(event: notify::Result<Event>) -> Vec<PathBuf> {
+    // Reads (including cargo's own during a re-run) must not trigger another run.
+    let Ok(event) = event else {
+        return vec![];
+    };
+    if !matches!(
+        event.kind,
+        EventKind::Create(_)
+            | EventKind::Remove(_)
+            | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Name(_) | ModifyKind::Any)
+    ) {
+        return vec![];
+    }
+    event
+        .paths
+        .into_iter()
+        .filter(|p| {
+            matches!(
+                p.file_name().and_then(|n| n.to_str()),
+                Some("Cargo.toml" | "Cargo.lock")
+            ) && !p
+                .components()
+                .any(|c| c == Component::Normal("target".as_ref()))
+        })
+        .collect()
+}
+
+pub fn print_delta(previous: &AnalyzeOut, current: &AnalyzeOut) {
+    println!(
+        "{} nodes ({:+}), {} edges ({:+})",
+        current.nodes,
+        current.nodes as i64 - previous.nodes as i64,
+        current.edges,
+        current.edges as i64 - previous.edges as i64
+    );
+
+    let before: HashMap<&str, usize> = previous
+        .rows
+        .iter()
+        .map(|r| (r.name.as_str(), r.rank))
+        .collect();
+    let after: HashMap<&str, usize> = current
+        .rows
+        .iter()
+        .map(|r| (r.name.as_str(), r.rank))
+        .collect();
+
+    let mut lines = Vec::new();
+    for row in &current.rows {
+        match before.get(row.name.as_str()) {
+            None => lines.push(format!("  + {:3}. {}", row.rank, row.name)),
+            Some(&old) if old != row.rank => {
+                lines.push(format!("  ~ {:3}. {} (was {})", row.rank, row.name, old))
+            }
+            _ => {}
+        }
+    }
+    for row in &previous.rows {
+        if !after.contains_key(row.name.as_str()) {
+            lines.push(format!("  - {:3}. {}", row.rank, row.name));
+        }
+    }
+
+    if lines.is_empty() {
+        println!("  no ranking changes");
+    } else {
+        for line in lines {
+            println!("{}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, DataChange};
+
+    fn event(kind: EventKind, path: &str) -> notify::Result<Event> {
+        Ok(Event::new(kind).add_path(PathBuf::from(path)))
+    }
+
+    #[test]
+    fn target_dir_is_filtered_out() {
+        let kind = EventKind::Modify(ModifyKind::Data(DataChange::Any));
+        assert!(relevant_paths(event(kind, "/ws/target/debug/Cargo.toml")).is_empty());
+    }
+
+    #[test]
+    fn access_is_ignored() {
+        let kind = EventKind::Access(AccessKind::Any);
+        assert!(relevant_paths(event(kind, "/ws/Cargo.toml")).is_empty());
+    }
+
+    #[test]
+    fn lockfile_create_is_kept() {
+        let kind = EventKind::Create(CreateKind::File);
+        assert_eq!(
+            relevant_paths(event(kind, "/ws/Cargo.lock")),
+            [PathBuf::from("/ws/Cargo.lock")]
+        );
+    }
+}
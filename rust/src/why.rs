@@ -0,0 +1,183 @@
+//! `pkgrank why`: shortest dependency paths between two packages.
+
+use crate::centrality::{self, PageRankConfig};
+use crate::graph::{DepGraph, find_package};
+use petgraph::prelude::*;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// One package on a path. `kinds` are the kinds of the edge leading into it.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Step {
+    pub name: String,
+    pub version: String,
+    pub pagerank: f64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub kinds: Vec<String>,
+    /// Targets the edge leading into it resolves for (`--targets` only).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WhyOut {
+    pub from: String,
+    pub to: String,
+    /// Number of hops on the shortest path, or `None` if `to` is unreachable.
+    pub distance: Option<usize>,
+    pub paths: Vec<Vec<Step>>,
+}
+
+pub fn why(
+    graph: &DepGraph,
+    from: &str,
+    to: &str,
+    max_paths: usize,
+    config: &PageRankConfig,
+) -> anyhow::Result<WhyOut> {
+    let source = find_package(graph, from)?;
+    let target = find_package(graph, to)?;
+
+    let paths = shortest_paths(graph, source, target, max_paths);
+    let (pagerank, _) = centrality::pagerank(graph, config);
+    let distance = paths.first().map(|p| p.len() - 1);
+
+    let paths = paths
+        .into_iter()
+        .map(|path| {
+            path.iter()
+                .enumerate()
+                .map(|(i, &idx)| {
+                    let (kinds, targets) = if i == 0 {
+                        (vec![], vec![])
+                    } else {
+                        let dep = &graph[graph.find_edge(path[i - 1], idx).unwrap()];
+                        (
+                            dep.kinds.iter().map(|k| k.to_string()).collect(),
+                            dep.targets.clone(),
+                        )
+                    };
+                    Step {
+                        name: graph[idx].name.clone(),
+                        version: graph[idx].version.clone(),
+                        pagerank: pagerank[idx.index()],
+                        kinds,
+                        targets,
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(WhyOut {
+        from: from.to_string(),
+        to: to.to_string(),
+        distance,
+        paths,
+    })
+}
+
+/// All shortest paths from `source` to `target` (at most `max_paths`), in name order.
+pub fn shortest_paths(
+    graph: &DepGraph,
+    source: NodeIndex,
+    target: NodeIndex,
+    max_paths: usize,
+) -> Vec<Vec<NodeIndex>> {
+    let n = graph.node_count();
+    let mut dist: Vec<Option<usize>> = vec![None; n];
+    let mut pred: Vec<Vec<NodeIndex>> = vec![vec![]; n];
+
+    dist[source.index()] = Some(0);
+    let mut queue = VecDeque::from([source]);
+
+    while let Some(v) = queue.pop_front() {
+        let d = dist[v.index()].unwrap();
+        if dist[target.index()].is_some_and(|t| d >= t) {
+            break;
+        }
+        for w in graph.neighbors_directed(v, Direction::Outgoing) {
+            match dist[w.index()] {
+                None => {
+                    dist[w.index()] = Some(d + 1);
+                    pred[w.index()].push(v);
+                    queue.push_back(w);
+                }
+                Some(dw) if dw == d + 1 => pred[w.index()].push(v),
+                _ => {}
+            }
+        }
+    }
+
+    if dist[target.index()].is_none() {
+        return vec![];
+    }
+    for p in &mut pred {
+        p.sort_by(|a, b| {
+            graph[*a]
+                .name
+                .cmp(&graph[*b].name)
+                .then_with(|| graph[*a].version.cmp(&graph[*b].version))
+        });
+        p.dedup();
+    }
+
+    // Walk predecessors back from the target; paths are built reversed.
+    let mut paths = Vec::new();
+    let mut stack = vec![vec![target]];
+    while let Some(partial) = stack.pop() {
+        if paths.len() >= max_paths {
+            break;
+        }
+        let head = *partial.last().unwrap();
+        if head == source {
+            paths.push(partial.into_iter().rev().collect());
+            continue;
+        }
+        for &p in pred[head.index()].iter().rev() {
+            let mut next = partial.clone();
+            next.push(p);
+            stack.push(next);
+        }
+    }
+    paths
+}
+
+pub fn print_text(out: &WhyOut) {
+    let Some(distance) = out.distance else {
+        println!("No dependency path from {} to {}", out.from, out.to);
+        return;
+    };
+
+    println!(
+        "Shortest paths from {} to {} ({} hops):",
+        out.from, out.to, distance
+    );
+    println!("{:─<50}", "");
+    for (i, path) in out.paths.iter().enumerate() {
+        let first = &path[0];
+        println!(
+            "{:3}. {} {} ({:.6})",
+            i + 1,
+            first.name,
+            first.version,
+            first.pagerank
+        );
+        for step in &path[1..] {
+            let targets = if step.targets.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", step.targets.join(", "))
+            };
+            println!(
+                "       -> {} {} ({:.6}; {}){}",
+                step.name,
+                step.version,
+                step.pagerank,
+                step.kinds.join(", "),
+                targets
+            );
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Every JSON report must come out byte-identical across runs, including
+//! the order of rows with equal scores.
+
+use std::process::Command;
+
+fn run(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_pkgrank"))
+        .args(args)
+        .arg(env!("CARGO_MANIFEST_DIR"))
+        .arg("--json")
+        .output()
+        .expect("failed to run pkgrank");
+    assert!(
+        output.status.success(),
+        "pkgrank {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn json_output_is_stable_across_runs() {
+    let commands: &[&[&str]] = &[
+        &["analyze", "-n", "1000"],
+        &["analyze", "-n", "1000", "--metric", "indegree"],
+        &["analyze", "-n", "1000", "--metric", "betweenness"],
+        &["analyze", "-n", "1000", "--dev", "--build", "--licenses"],
+        &["impact", "serde"],
+        &["why", "--from", "pkgrank", "--to", "unicode-ident"],
+        &["dupes"],
+        &["owners"],
+        &["modules"],
+    ];
+    for args in commands {
+        let first = run(args);
+        let second = run(args);
+        assert_eq!(first, second, "pkgrank {:?} differs between runs", args);
+    }
+}
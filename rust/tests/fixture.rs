@@ -0,0 +1,74 @@
+//! A generated fixture must come back from analyze with exactly the crates
+//! and edges it was generated with.
+
+use std::process::Command;
+
+fn pkgrank(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_pkgrank"))
+        .args(args)
+        .output()
+        .expect("failed to run pkgrank");
+    assert!(
+        output.status.success(),
+        "pkgrank {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn nested_fixture_round_trips_through_analyze() {
+    let dir = std::env::temp_dir().join(format!("pkgrank-fixture-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let dir_arg = dir.to_str().unwrap();
+
+    pkgrank(&[
+        "gen-fixture",
+        dir_arg,
+        "--crates",
+        "12",
+        "--edges",
+        "30",
+        "--layout",
+        "nested",
+        "--seed",
+        "7",
+    ]);
+    let out = pkgrank(&["analyze", dir_arg, "--nested", "--json"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let out: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(out["nodes"], 12);
+    assert_eq!(out["edges"], 30);
+    assert_eq!(
+        out["diagnostics"]["nested_workspaces_skipped"],
+        serde_json::json!([])
+    );
+}
+
+#[test]
+fn merged_workspaces_are_scored_as_one_graph() {
+    let base = std::env::temp_dir().join(format!("pkgrank-merge-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&base);
+    let (first, second) = (base.join("first"), base.join("second"));
+    let (first_arg, second_arg) = (first.to_str().unwrap(), second.to_str().unwrap());
+
+    pkgrank(&["gen-fixture", first_arg, "--crates", "5", "--edges", "6"]);
+    pkgrank(&["gen-fixture", second_arg, "--crates", "4", "--edges", "3"]);
+    let out = pkgrank(&[
+        "analyze", first_arg, "--merge", second_arg, "-n", "20", "--json",
+    ]);
+    std::fs::remove_dir_all(&base).unwrap();
+
+    let out: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(out["nodes"], 9);
+    assert_eq!(out["edges"], 9);
+    let labelled = out["rows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|row| row["workspace"] == second_arg)
+        .count();
+    assert_eq!(labelled, 4);
+}
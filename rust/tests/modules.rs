@@ -0,0 +1,48 @@
+//! `modules --report-unused` must not list items used inside their own
+//! module.
+
+use std::process::Command;
+
+#[test]
+fn items_used_in_their_own_module_are_not_unused() {
+    let dir = std::env::temp_dir().join(format!("pkgrank-unused-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"unused_fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("src/lib.rs"),
+        "mod util;\n\npub fn run() {\n    util::g();\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("src/util.rs"),
+        "pub(crate) fn g() {\n    helper();\n}\n\npub(crate) fn helper() {}\n\npub(crate) fn dead() {}\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_pkgrank"))
+        .args([
+            "modules",
+            dir.to_str().unwrap(),
+            "--report-unused",
+            "--json",
+        ])
+        .output()
+        .expect("failed to run pkgrank");
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let out: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        out["unused"]["items"],
+        serde_json::json!(["crate::util::dead"])
+    );
+}
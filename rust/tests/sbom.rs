@@ -0,0 +1,96 @@
+//! SBOM identifiers must be unique even where name and version are not,
+//! licenses must be valid SPDX expressions, and only registry packages get
+//! a purl.
+
+use std::process::Command;
+
+#[test]
+fn sbom_ids_licenses_and_purls() {
+    let dir = std::env::temp_dir().join(format!("pkgrank-sbom-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for sub in ["app/src", "one/util/src", "two/util/src"] {
+        std::fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    std::fs::write(
+        dir.join("app/Cargo.toml"),
+        format!("[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\nlicense = \"MIT/Apache-2.0\"\n\n\
+         [dependencies]\nutil = {{ path = \"../one/util\" }}\nutil2 = {{ package = \"util\", git = \"file://{}\" }}\n\n\
+         [workspace]\n",
+            dir.join("two/util").display()
+        ),
+    )
+    .unwrap();
+    std::fs::write(dir.join("app/src/lib.rs"), "").unwrap();
+    for sub in ["one", "two"] {
+        std::fs::write(
+            dir.join(sub).join("util/Cargo.toml"),
+            "[package]\nname = \"util\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join(sub).join("util/src/lib.rs"), "").unwrap();
+    }
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir.join("two/util"))
+            .status()
+            .expect("failed to run git");
+        assert!(status.success(), "git {:?}", args);
+    };
+    git(&["init", "-q"]);
+    git(&["add", "-A"]);
+    git(&[
+        "-c",
+        "user.name=t",
+        "-c",
+        "user.email=t@t",
+        "commit",
+        "-qm",
+        "util",
+    ]);
+
+    let sbom = |format: &str| -> serde_json::Value {
+        let output = Command::new(env!("CARGO_BIN_EXE_pkgrank"))
+            .args([
+                "sbom",
+                dir.join("app").to_str().unwrap(),
+                "--format",
+                format,
+            ])
+            .output()
+            .expect("failed to run pkgrank");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice(&output.stdout).unwrap()
+    };
+    let (cyclonedx, spdx) = (sbom("cyclonedx"), sbom("spdx"));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let components = cyclonedx["components"].as_array().unwrap();
+    assert_eq!(components.len(), 3);
+    let mut refs: Vec<&str> = components
+        .iter()
+        .map(|c| c["bom-ref"].as_str().unwrap())
+        .collect();
+    refs.sort();
+    refs.dedup();
+    assert_eq!(refs.len(), 3);
+    assert!(components.iter().all(|c| c.get("purl").is_none()));
+    let app = components.iter().find(|c| c["name"] == "app").unwrap();
+    assert_eq!(app["licenses"][0]["expression"], "MIT OR Apache-2.0");
+
+    let packages = spdx["packages"].as_array().unwrap();
+    let mut ids: Vec<&str> = packages
+        .iter()
+        .map(|p| p["SPDXID"].as_str().unwrap())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), 3);
+    assert!(packages.iter().all(|p| p.get("externalRefs").is_none()));
+    let app = packages.iter().find(|p| p["name"] == "app").unwrap();
+    assert_eq!(app["licenseDeclared"], "MIT OR Apache-2.0");
+}
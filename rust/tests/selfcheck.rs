@@ -0,0 +1,16 @@
+//! The committed golden result must match a fresh run on pkgrank itself.
+
+use std::process::Command;
+
+#[test]
+fn selfcheck_matches_golden() {
+    let output = Command::new(env!("CARGO_BIN_EXE_pkgrank"))
+        .arg("selfcheck")
+        .output()
+        .expect("failed to run pkgrank");
+    assert!(
+        output.status.success(),
+        "selfcheck failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
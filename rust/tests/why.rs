@@ -0,0 +1,38 @@
+//! The pagerank `why` shows on each hop must be the score `analyze` gives
+//! the same package under the same graph and PageRank flags.
+
+use std::process::Command;
+
+fn run(args: &[&str]) -> serde_json::Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_pkgrank"))
+        .args(args)
+        .arg(env!("CARGO_MANIFEST_DIR"))
+        .args(["--dev", "--weight-dev", "0.3", "--damping", "0.6", "--json"])
+        .output()
+        .expect("failed to run pkgrank");
+    assert!(
+        output.status.success(),
+        "pkgrank {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn why_scores_match_analyze() {
+    let analyze = run(&["analyze", "-n", "100000"]);
+    let why = run(&["why", "--from", "pkgrank", "--to", "unicode-ident"]);
+
+    let steps = why["paths"][0].as_array().unwrap();
+    assert!(steps.len() > 1);
+    for step in steps {
+        let row = analyze["rows"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["name"] == step["name"] && r["version"] == step["version"])
+            .unwrap_or_else(|| panic!("{} not ranked by analyze", step["name"]));
+        assert_eq!(row["score"], step["pagerank"], "{}", step["name"]);
+    }
+}